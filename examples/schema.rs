@@ -3,7 +3,7 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use finschia_lottery::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, InfoResponse};
+use finschia_lottery::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, InfoResponse, SudoMsg};
 // use finschia_lottery::state::State;
 
 fn main() {
@@ -15,5 +15,6 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
     export_schema(&schema_for!(InfoResponse), &out_dir);
 }
@@ -0,0 +1,160 @@
+use cosmwasm_std::{Addr, Binary, DepsMut, Empty, MessageInfo, Response, Storage};
+use cw_storage_plus::Map;
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
+use crate::event::{AccessListUpdatedEvent, AllowlistJoinedEvent, AllowlistSetEvent, Event};
+use crate::roles::ensure_role;
+use crate::state::{
+    AllowlistConfig, Role, ALLOWLIST, ALLOWLIST_CONFIG, BLOCKLIST, PROVEN_MEMBERS,
+};
+
+// bounds the storage writes of a single list update
+const MAX_BATCH: usize = 100;
+// deep enough for a tree of 2^64 addresses
+const MAX_PROOF_LEN: usize = 64;
+
+/// Whether `addr` may play or hold LTT. The blocklist always applies, the allowlist only
+/// while it is enabled. A member proven under an earlier merkle root is not allowed.
+pub fn ensure_allowed(storage: &dyn Storage, addr: &Addr) -> Result<(), ContractError> {
+    if BLOCKLIST.has(storage, addr) {
+        return Err(ContractError::Blocked {
+            addr: addr.to_string(),
+        });
+    }
+    let allowlist = match ALLOWLIST_CONFIG.may_load(storage)? {
+        Some(config) if config.enabled => config,
+        _ => return Ok(()),
+    };
+    if !ALLOWLIST.has(storage, addr)
+        && (allowlist.merkle_root.is_none()
+            || PROVEN_MEMBERS.may_load(storage, addr)? != allowlist.merkle_root)
+    {
+        return Err(ContractError::NotAllowlisted {
+            addr: addr.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Check `proof` leads from the address to `root`. Leaves are the sha256 of the address,
+/// inner nodes the sha256 of their two children in ascending order.
+pub fn verify_merkle_proof(root: &[u8], addr: &Addr, proof: &[Binary]) -> bool {
+    let mut hash = Sha256::digest(addr.as_bytes()).to_vec();
+    for sibling in proof {
+        let (first, second) = if hash.as_slice() <= sibling.as_slice() {
+            (hash.as_slice(), sibling.as_slice())
+        } else {
+            (sibling.as_slice(), hash.as_slice())
+        };
+        hash = Sha256::new()
+            .chain_update(first)
+            .chain_update(second)
+            .finalize()
+            .to_vec();
+    }
+    hash == root
+}
+
+fn update_list(
+    deps: DepsMut,
+    info: MessageInfo,
+    map: Map<&Addr, Empty>,
+    list: &str,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if add.len() + remove.len() == 0 || add.len() + remove.len() > MAX_BATCH {
+        return Err(ContractError::InvalidParams {});
+    }
+    for addr in add.iter() {
+        map.save(deps.storage, &deps.api.addr_validate(addr)?, &Empty {})?;
+    }
+    for addr in remove.iter() {
+        map.remove(deps.storage, &deps.api.addr_validate(addr)?);
+    }
+
+    let mut rsp = Response::new();
+    AccessListUpdatedEvent {
+        list,
+        added: add.len() as u64,
+        removed: remove.len() as u64,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// Update the written members. A removed member is also forgotten as a proven one.
+pub fn handle_update_allowlist(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let rsp = update_list(deps.branch(), info, ALLOWLIST, "allowlist", add, remove.clone())?;
+    for addr in remove.iter() {
+        PROVEN_MEMBERS.remove(deps.storage, &deps.api.addr_validate(addr)?);
+    }
+    Ok(rsp)
+}
+
+pub fn handle_update_blocklist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    update_list(deps, info, BLOCKLIST, "blocklist", add, remove)
+}
+
+/// Turn the allowlist mode on or off. Addresses under `merkle_root` join with a proof
+/// instead of being written by the admin, and join again when the root changes.
+pub fn handle_set_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+    merkle_root: Option<Binary>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if merkle_root.as_ref().is_some_and(|root| root.len() != 32) {
+        return Err(ContractError::InvalidParams {});
+    }
+    ALLOWLIST_CONFIG.save(
+        deps.storage,
+        &AllowlistConfig {
+            enabled,
+            merkle_root: merkle_root.clone(),
+        },
+    )?;
+
+    let mut rsp = Response::new();
+    AllowlistSetEvent {
+        enabled,
+        merkle_root: &merkle_root.map(|r| r.to_base64()).unwrap_or_default(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+pub fn handle_join_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    proof: Vec<Binary>,
+) -> Result<Response, ContractError> {
+    let root = ALLOWLIST_CONFIG
+        .may_load(deps.storage)?
+        .and_then(|c| c.merkle_root)
+        .ok_or(ContractError::InvalidParams {})?;
+    if proof.len() > MAX_PROOF_LEN || !verify_merkle_proof(&root, &info.sender, &proof) {
+        return Err(ContractError::InvalidMerkleProof {});
+    }
+    PROVEN_MEMBERS.save(deps.storage, &info.sender, &root)?;
+
+    let mut rsp = Response::new();
+    AllowlistJoinedEvent {
+        who: info.sender.as_str(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
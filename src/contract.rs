@@ -1,36 +1,185 @@
 #[cfg(not(feature = "library"))]
-use cosmwasm_std::{entry_point, Addr};
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Order, Response, StdResult, SubMsg,
-    Uint128,
+    from_binary, to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order,
+    Reply, Response, StdResult, Storage, SubMsg, SubMsgResult, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
+use crate::access::{
+    ensure_allowed, handle_join_allowlist, handle_set_allowlist, handle_update_allowlist,
+    handle_update_blocklist,
+};
+use crate::dividends::{distribute_dividends, handle_claim_dividends, settle_dividends};
 use crate::error::ContractError;
-use crate::event::{ClosedInvestmentEvent, Event, InvestedEvent};
-use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::event::{
+    ClosedInvestmentEvent, DenomUpdatedEvent, Event, InvestedEvent, LottoDrawnEvent,
+    PayoutFailedEvent, PrizeClaimedEvent, TicketNftInstantiatedEvent, TokenTransferredEvent,
+};
+use crate::msg::{ExecuteMsg, InstantiateMsg, ReceiveMsg, SudoMsg};
+use crate::expiry::{
+    claims_deadline, credit_claim, forget_claims, handle_sweep_expired, validate_claim_expiry,
+};
+use crate::fees::{accrue_fee, handle_withdraw_fees};
+use crate::factory::{
+    handle_add_template, handle_create_lottery, handle_execute_on_lotteries,
+    handle_instantiate_lottery_reply, handle_remove_template, INSTANTIATE_LOTTERY_REPLY_ID,
+};
+use crate::limits::{apply_invest_limits, ensure_invest_limits, validate_invest_limits};
+use crate::lotto::{
+    draw_lotto, handle_buy_lotto_ticket, handle_commit_lotto_chain, handle_reveal_lotto_seed,
+    top_tier_winners, validate_lotto_config,
+};
+use crate::nft::{instantiate_collection_msg, parse_instantiated_address, ticket_owner};
+use crate::pause::{ensure_not_paused, handle_pause, handle_unpause, operation_of};
+use crate::redeem::{handle_redeem_for_ticket, with_bonus_weights};
+use crate::referral::{bind_referrer, credit_referrals, handle_claim_referral_rewards};
+use crate::responsible::{handle_set_deposit_limit, handle_set_self_exclusion, record_deposit};
+use crate::reinvest::{handle_set_auto_reinvest, reinvest_winnings};
+use crate::roles::{ensure_role, handle_grant_role, handle_revoke_role};
+use crate::scratch::{
+    handle_commit_scratch_chain, handle_fund_bankroll, handle_refund_scratch,
+    handle_reveal_scratch, handle_scratch, handle_withdraw_bankroll, validate_scratch_config,
+};
+use crate::sponsor::handle_sponsor;
+use crate::staking::{
+    delegate_msgs, handle_rebalance, handle_unbond,
+    handle_unbond_reply, handle_update_validators, handle_withdraw_principal,
+    track_withdrawn_rewards, validate_validators, withdraw_reward_msgs, UNBOND_REPLY_ID,
+};
 use crate::state::{
-    ContractInfo, Current, Investment, Investor, TokenInfo, Winner, BALANCES, CONTRACT_INFO,
-    CURRENT, INVESTMENTS, INVESTORS, TOKEN_INFO,
+    ContractInfo, Current, Investment, Investor, Payout, Pool, Role, TokenInfo, Winner,
+    ACCEPTED_DENOMS, BALANCES, CLAIMABLE, CLAIM_EXPIRY, CONTRACT_INFO, CURRENT, DIVIDEND_SHARE,
+    FACTORY, ROLES,
+    INVESTMENTS, INVESTORS, INVEST_LIMITS, LOTTO_CONFIG, PENDING_PAYOUTS, POOLS, POOL_INVESTORS,
+    PRINCIPALS, REDEEM_RATE, REFERRAL_SHARE, ROUND_DURATION, SCRATCH_BANKROLL, SCRATCH_CONFIG,
+    STAKING, STAKING_CONFIG, TICKET_CONFIG, TICKET_NFT, TOKEN_INFO, UNDRAWN_REWARDS,
+    VESTING_CONFIG,
+};
+use crate::subscription::{
+    enter_subscribers, handle_process_subscriptions, handle_subscribe, handle_unsubscribe,
+    SUBSCRIPTION_BATCH,
 };
-// use sha2::{Digest, Sha256};
+use crate::sudo::{handle_close_due_rounds, handle_force_cancel};
+use crate::tickets::{draw_tickets, handle_buy_tickets};
+use crate::vesting::{handle_claim_vested, vest_prize};
+use sha2::{Digest, Sha256};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:finschia-lottery";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// reply ids, a prize payout uses PAYOUT_REPLY_ID + its index in PENDING_PAYOUTS, the
+// other ids stay below PAYOUT_REPLY_ID
+pub const INSTANTIATE_TICKET_NFT_REPLY_ID: u64 = 1;
+pub const PAYOUT_REPLY_ID: u64 = 1000;
+
+// accepted cw20 tokens are listed as "cw20:<contract address>"
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     if msg.use_denom.is_empty() {
         return Err(ContractError::InvalidParams {});
     }
+    if let Some(tickets) = &msg.tickets {
+        if tickets.price.is_zero() || msg.no_loss.is_some() {
+            return Err(ContractError::InvalidParams {});
+        }
+        TICKET_CONFIG.save(deps.storage, tickets)?;
+    }
+    if let Some(lotto) = &msg.lotto {
+        if msg.tickets.is_some() || msg.no_loss.is_some() {
+            return Err(ContractError::InvalidParams {});
+        }
+        validate_lotto_config(lotto, msg.owner_ratio)?;
+        LOTTO_CONFIG.save(deps.storage, lotto)?;
+    }
+    if let Some(scratch) = &msg.scratch {
+        validate_scratch_config(scratch)?;
+        SCRATCH_CONFIG.save(deps.storage, scratch)?;
+        SCRATCH_BANKROLL.save(deps.storage, &Uint128::zero())?;
+    }
+    let mut submsgs = vec![];
+    if let Some(nft) = msg.ticket_nft {
+        if msg.tickets.is_none() {
+            return Err(ContractError::InvalidParams {});
+        }
+        submsgs.push(instantiate_collection_msg(
+            nft.code_id,
+            nft.name,
+            nft.symbol,
+            &env.contract.address,
+            INSTANTIATE_TICKET_NFT_REPLY_ID,
+        )?);
+    }
+    if let Some(limits) = &msg.limits {
+        // the limits are in `use_denom`, they cannot cap the pools of other denoms
+        if msg.accepted_denoms.as_ref().is_some_and(|d| !d.is_empty()) {
+            return Err(ContractError::InvalidParams {});
+        }
+        validate_invest_limits(limits)?;
+        INVEST_LIMITS.save(deps.storage, limits)?;
+    }
+    if let Some(share) = msg.referral_share {
+        if share > 100 {
+            return Err(ContractError::InvalidParams {});
+        }
+        REFERRAL_SHARE.save(deps.storage, &share)?;
+    }
+    if let Some(rate) = msg.redeem_rate {
+        if rate.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        REDEEM_RATE.save(deps.storage, &rate)?;
+    }
+    if let Some(share) = msg.dividend_share {
+        if share > 100 {
+            return Err(ContractError::InvalidParams {});
+        }
+        DIVIDEND_SHARE.save(deps.storage, &share)?;
+    }
+    if let Some(expiry) = &msg.claim_expiry {
+        validate_claim_expiry(deps.api, expiry)?;
+        CLAIM_EXPIRY.save(deps.storage, expiry)?;
+    }
+    if let Some(vesting) = &msg.vesting {
+        if vesting.duration == 0 {
+            return Err(ContractError::InvalidParams {});
+        }
+        VESTING_CONFIG.save(deps.storage, vesting)?;
+    }
+    if let Some(duration) = msg.round_duration {
+        if duration == 0 {
+            return Err(ContractError::InvalidParams {});
+        }
+        ROUND_DURATION.save(deps.storage, &duration)?;
+    }
+    if let Some(staking) = &msg.no_loss {
+        validate_validators(&staking.validators)?;
+        STAKING_CONFIG.save(deps.storage, staking)?;
+        STAKING.save(deps.storage, &Uint128::zero())?;
+    }
+    // a lottery instantiated for another owner, e.g. by a factory, leaves the instantiator
+    // every role but the treasurer, so it can still manage the lottery
+    let owner = match &msg.owner {
+        Some(owner) => deps.api.addr_validate(owner)?,
+        None => info.sender.clone(),
+    };
+    if owner != info.sender {
+        for role in [Role::Admin, Role::Operator, Role::Guardian] {
+            ROLES.save(deps.storage, (role.as_str(), &info.sender), &Empty {})?;
+        }
+    }
+    FACTORY.save(deps.storage, &msg.factory.unwrap_or_default())?;
     let contract = ContractInfo {
-        owner: info.sender.clone(),
+        owner: owner.clone(),
         use_denom: msg.use_denom.clone(),
         exchange_ratio: msg.exchange_ratio,
         min_exchange_amount: msg.min_exchange_amount,
@@ -48,9 +197,13 @@ pub fn instantiate(
         round: 1u32,
         exchange_round: 1u32,
     };
+    for accepted in msg.accepted_denoms.unwrap_or_default() {
+        validate_denom(deps.as_ref(), &contract, &accepted.denom)?;
+        ACCEPTED_DENOMS.save(deps.storage, &accepted.denom, &accepted.exchange_ratio)?;
+    }
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     CONTRACT_INFO.save(deps.storage, &contract)?;
-    TOKEN_INFO.save(deps.storage, &token)?;
+    TOKEN_INFO.save(deps.storage, &token, env.block.height)?;
     CURRENT.save(deps.storage, &current)?;
 
     let new_investment = Investment {
@@ -59,12 +212,19 @@ pub fn instantiate(
         in_progress: true,
         first_winner: None,
         second_winner: None,
+        ticket_count: 0,
+        deadline: msg.round_duration.map(|d| env.block.time.plus_seconds(d)),
+        cancelled: false,
+        sponsored_amount: Uint128::zero(),
+        claims_expire_at: None,
+        swept_amount: Uint128::zero(),
     };
     INVESTMENTS.save(deps.storage, current.round.to_string(), &new_investment)?;
 
     Ok(Response::new()
+        .add_submessages(submsgs)
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", owner))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -74,41 +234,164 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    if let Some(operation) = operation_of(&msg) {
+        ensure_not_paused(deps.storage, operation)?;
+    }
     match msg {
-        ExecuteMsg::Invest {} => handle_invest(deps, &info),
+        ExecuteMsg::Invest { referrer } => handle_invest(deps, env, &info, referrer),
+        ExecuteMsg::BuyTickets { count, referrer } => {
+            handle_buy_tickets(deps, env, info, count, referrer)
+        }
+        ExecuteMsg::BuyLottoTicket { numbers, referrer } => {
+            handle_buy_lotto_ticket(deps, env, info, numbers, referrer)
+        }
+        ExecuteMsg::CommitLottoChain { anchor, length } => {
+            handle_commit_lotto_chain(deps, info, anchor, length)
+        }
+        ExecuteMsg::RevealLottoSeed { preimage } => handle_reveal_lotto_seed(deps, info, preimage),
+        ExecuteMsg::Receive(wrapper) => handle_receive(deps, env, info, wrapper),
         ExecuteMsg::CloseInvestment {} => handle_close_investment(deps, env, info),
-        ExecuteMsg::TransferToken { to, amount } => handl_transfer_token(deps, info, to, amount),
+        ExecuteMsg::TransferToken { to, amount } => {
+            handl_transfer_token(deps, env, info, to, amount)
+        }
+        ExecuteMsg::Unbond { amount } => handle_unbond(deps, info, amount),
+        ExecuteMsg::WithdrawPrincipal {} => handle_withdraw_principal(deps, env, info),
+        ExecuteMsg::UpdateValidators { validators } => {
+            handle_update_validators(deps, env, info, validators)
+        }
+        ExecuteMsg::Rebalance {} => handle_rebalance(deps, env),
+        ExecuteMsg::Scratch {} => handle_scratch(deps, env, info),
+        ExecuteMsg::CommitScratchChain { anchor, length } => {
+            handle_commit_scratch_chain(deps, info, anchor, length)
+        }
+        ExecuteMsg::RevealScratch { preimages } => handle_reveal_scratch(deps, info, preimages),
+        ExecuteMsg::RefundScratch {} => handle_refund_scratch(deps, env),
+        ExecuteMsg::FundBankroll {} => handle_fund_bankroll(deps, info),
+        ExecuteMsg::WithdrawBankroll { amount } => handle_withdraw_bankroll(deps, info, amount),
+        ExecuteMsg::Pause { reason, operations } => {
+            handle_pause(deps, env, info, reason, operations)
+        }
+        ExecuteMsg::Unpause {} => handle_unpause(deps, info),
+        ExecuteMsg::GrantRole { role, addr } => handle_grant_role(deps, info, role, addr),
+        ExecuteMsg::RevokeRole { role, addr } => handle_revoke_role(deps, info, role, addr),
+        ExecuteMsg::AddTemplate { name, code_id, msg } => {
+            handle_add_template(deps, info, name, code_id, *msg)
+        }
+        ExecuteMsg::RemoveTemplate { name } => handle_remove_template(deps, info, name),
+        ExecuteMsg::CreateLottery { template, label } => {
+            handle_create_lottery(deps, env, info, template, label)
+        }
+        ExecuteMsg::ExecuteOnLotteries {
+            msg,
+            start_after,
+            limit,
+        } => handle_execute_on_lotteries(deps, info, *msg, start_after, limit),
+        ExecuteMsg::ClaimPrize {} => handle_claim_prize(deps, info),
+        ExecuteMsg::AddDenom {
+            denom,
+            exchange_ratio,
+        } => handle_add_denom(deps, info, denom, exchange_ratio),
+        ExecuteMsg::RemoveDenom { denom } => handle_remove_denom(deps, info, denom),
+        ExecuteMsg::UpdateAllowlist { add, remove } => {
+            handle_update_allowlist(deps, info, add, remove)
+        }
+        ExecuteMsg::UpdateBlocklist { add, remove } => {
+            handle_update_blocklist(deps, info, add, remove)
+        }
+        ExecuteMsg::SetAllowlist {
+            enabled,
+            merkle_root,
+        } => handle_set_allowlist(deps, info, enabled, merkle_root),
+        ExecuteMsg::JoinAllowlist { proof } => handle_join_allowlist(deps, info, proof),
+        ExecuteMsg::SetSelfExclusion { until } => {
+            handle_set_self_exclusion(deps, env, info, until)
+        }
+        ExecuteMsg::SetDepositLimit { per_day, per_week } => {
+            handle_set_deposit_limit(deps, env, info, per_day, per_week)
+        }
+        ExecuteMsg::ClaimReferralRewards {} => handle_claim_referral_rewards(deps, info),
+        ExecuteMsg::Sponsor { round, message } => handle_sponsor(deps, info, round, message),
+        ExecuteMsg::Subscribe {
+            rounds,
+            amount_per_round,
+            referrer,
+        } => handle_subscribe(deps, env, info, rounds, amount_per_round, referrer),
+        ExecuteMsg::Unsubscribe {} => handle_unsubscribe(deps, info),
+        ExecuteMsg::ProcessSubscriptions { limit } => {
+            handle_process_subscriptions(deps, env, limit)
+        }
+        ExecuteMsg::SetAutoReinvest { enabled, percent } => {
+            handle_set_auto_reinvest(deps, info, enabled, percent)
+        }
+        ExecuteMsg::ClaimVested {} => handle_claim_vested(deps, env, info),
+        ExecuteMsg::SweepExpired { round } => handle_sweep_expired(deps, env, round),
+        ExecuteMsg::ClaimDividends {} => handle_claim_dividends(deps, info),
+        ExecuteMsg::RedeemForTicket { amount } => handle_redeem_for_ticket(deps, env, info, amount),
+        ExecuteMsg::WithdrawFees {} => handle_withdraw_fees(deps, info),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::CloseDueRounds {} => handle_close_due_rounds(deps, env),
+        SudoMsg::ForceCancel { round } => handle_force_cancel(deps, env, round),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_TICKET_NFT_REPLY_ID => handle_instantiate_ticket_nft_reply(deps, msg),
+        INSTANTIATE_LOTTERY_REPLY_ID => handle_instantiate_lottery_reply(deps, msg),
+        UNBOND_REPLY_ID => handle_unbond_reply(deps, msg),
+        id if is_payout_reply(deps.storage, id)? => {
+            handle_payout_reply(deps, id - PAYOUT_REPLY_ID, msg.result)
+        }
+        id => Err(ContractError::UnknownReplyId { id }),
     }
 }
 
-fn mint_token(
+pub(crate) fn mint_token(
     deps: DepsMut,
-    _rsp: &Response,
+    env: &Env,
     to: &Addr,
     amount: Uint128,
 ) -> Result<(), ContractError> {
+    let height = env.block.height;
+    settle_dividends(deps.storage, to)?;
     BALANCES.update(
         deps.storage,
         to,
+        height,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_add(amount)?)
         },
     )?;
-    TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
-        info.total_supply = info.total_supply.checked_add(amount)?;
-        Ok(info)
-    })?;
-
-    // TokenTransferredEvent {
-    //     from: "",
-    //     to: to.as_str(),
-    //     amount,
-    // }
-    // .add_attributes(rsp);
+    let mut token = TOKEN_INFO.load(deps.storage)?;
+    token.total_supply = token.total_supply.checked_add(amount)?;
+    TOKEN_INFO.save(deps.storage, &token, height)?;
 
     Ok(())
 }
 
+/// Burn `amount` of the LTT of `from`, the counterpart of `mint_token`.
+pub(crate) fn burn_token(
+    storage: &mut dyn Storage,
+    env: &Env,
+    from: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    let height = env.block.height;
+    settle_dividends(storage, from)?;
+    BALANCES.update(storage, from, height, |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default().checked_sub(amount)?)
+    })?;
+    let mut token = TOKEN_INFO.load(storage)?;
+    token.total_supply = token.total_supply.checked_sub(amount)?;
+    TOKEN_INFO.save(storage, &token, height)
+}
+
 pub fn one_coin(info: &MessageInfo) -> Result<Coin, ContractError> {
     match info.funds.len() {
         0 => Err(ContractError::NoFunds {}),
@@ -133,9 +416,80 @@ pub fn must_pay(info: &MessageInfo, denom: &str) -> Result<Uint128, ContractErro
     }
 }
 
-pub fn handle_invest(deps: DepsMut, info: &MessageInfo) -> Result<Response, ContractError> {
+/// Denom of a cw20 token in pools, claimable balances and payouts
+pub fn cw20_denom(token: &Addr) -> String {
+    format!("{}{}", CW20_DENOM_PREFIX, token)
+}
+
+fn validate_denom(deps: Deps, contract: &ContractInfo, denom: &str) -> Result<(), ContractError> {
+    if denom.is_empty() || denom == contract.use_denom {
+        return Err(ContractError::InvalidParams {});
+    }
+    if let Some(token) = denom.strip_prefix(CW20_DENOM_PREFIX) {
+        deps.api.addr_validate(token)?;
+    }
+    Ok(())
+}
+
+/// LTT exchange ratio of an accepted denom
+pub fn exchange_ratio_of(
+    storage: &dyn Storage,
+    contract: &ContractInfo,
+    denom: &str,
+) -> Result<u128, ContractError> {
+    if denom == contract.use_denom {
+        return Ok(contract.exchange_ratio);
+    }
+    ACCEPTED_DENOMS
+        .may_load(storage, denom)?
+        .ok_or_else(|| ContractError::ExtraDenom(denom.to_string()))
+}
+
+pub fn handle_invest(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    let coin = one_coin(info)?;
+    if coin.denom.starts_with(CW20_DENOM_PREFIX) {
+        return Err(ContractError::ExtraDenom(coin.denom));
+    }
+    invest(deps, &env, &info.sender, coin, referrer)
+}
+
+pub fn handle_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // the sender is the cw20 contract, the investor comes from the wrapper
+    let investor = deps.api.addr_validate(&wrapper.sender)?;
+    let coin = Coin::new(wrapper.amount.u128(), cw20_denom(&info.sender));
+    if coin.amount.is_zero() {
+        return Err(ContractError::NoFunds {});
+    }
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::Invest { referrer } => invest(deps, &env, &investor, coin, referrer),
+    }
+}
+
+fn invest(
+    mut deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    coin: Coin,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    if TICKET_CONFIG.may_load(deps.storage)?.is_some()
+        || LOTTO_CONFIG.may_load(deps.storage)?.is_some()
+    {
+        return Err(ContractError::TicketsOnly {});
+    }
+    ensure_allowed(deps.storage, sender)?;
     let contract = CONTRACT_INFO.load(deps.storage)?;
-    let amount = must_pay(info, contract.use_denom.as_str())?;
+    let exchange_ratio = exchange_ratio_of(deps.storage, &contract, &coin.denom)?;
 
     let round = CURRENT.load(deps.storage)?.round;
 
@@ -143,103 +497,390 @@ pub fn handle_invest(deps: DepsMut, info: &MessageInfo) -> Result<Response, Cont
     let mut investment = INVESTMENTS
         .may_load(deps.storage, round.to_string())?
         .ok_or(ContractError::InvalidRound { round })?;
-    investment.total_amount = investment.total_amount + amount;
-    INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
-    // INVESTORS.save(deps.storage, (round.to_string(), &info.sender), &amount)?;
-    INVESTORS.update(deps.storage, (round.to_string(), &info.sender), |a| -> StdResult<_>{
-        Ok(a.unwrap_or_default().checked_add(amount)?)
-    })?;
+    // the limits may accept only part of the amount, the rest is refunded
+    let (amount, refund) = if coin.denom == contract.use_denom {
+        apply_invest_limits(deps.storage, &investment, sender, coin.amount)?
+    } else {
+        (coin.amount, Uint128::zero())
+    };
+    // deposit limits count `use_denom` only, self-exclusion applies to every denom
+    let deposit = if coin.denom == contract.use_denom {
+        amount
+    } else {
+        Uint128::zero()
+    };
+    record_deposit(deps.storage, env, sender, deposit)?;
+    bind_referrer(deps.storage, deps.api, sender, referrer)?;
+    if coin.denom == contract.use_denom {
+        investment.total_amount += amount;
+        INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
+        // INVESTORS.save(deps.storage, (round.to_string(), sender), &amount)?;
+        INVESTORS.update(deps.storage, (round.to_string(), sender), |a| -> StdResult<_>{
+            Ok(a.unwrap_or_default().checked_add(amount)?)
+        })?;
+    } else {
+        // no-loss mode only stakes the bonded denom
+        if STAKING_CONFIG.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::MissingDenom(contract.use_denom));
+        }
+        // other denoms are kept in their own pool
+        POOLS.update(
+            deps.storage,
+            (round.to_string(), coin.denom.as_str()),
+            |p| -> StdResult<_> {
+                let mut pool = p.unwrap_or_else(|| Pool::new(&coin.denom));
+                pool.total_amount = pool.total_amount.checked_add(amount)?;
+                Ok(pool)
+            },
+        )?;
+        POOL_INVESTORS.update(
+            deps.storage,
+            (round.to_string(), coin.denom.as_str(), sender),
+            |a| -> StdResult<_> { Ok(a.unwrap_or_default().checked_add(amount)?) },
+        )?;
+    }
 
     // calculate token to mint
-    let exchange_ratio = CONTRACT_INFO.load(deps.storage)?.exchange_ratio;
     let exchange_amount = amount
         .checked_mul(Uint128::new(exchange_ratio))
         .map_err(|e| ContractError::CustomError { val: e.to_string() })?;
 
     let mut rsp = Response::default();
+    if !refund.is_zero() {
+        rsp = rsp.add_message(BankMsg::Send {
+            to_address: sender.to_string(),
+            amount: vec![Coin::new(refund.u128(), &coin.denom)],
+        });
+    }
+
+    // no-loss mode: keep the principal and delegate it
+    if let Some(staking) = STAKING_CONFIG.may_load(deps.storage)? {
+        PRINCIPALS.update(deps.storage, sender, |p| -> StdResult<_> {
+            Ok(p.unwrap_or_default().checked_add(amount)?)
+        })?;
+        STAKING.update(deps.storage, |s| -> StdResult<_> { Ok(s.checked_add(amount)?) })?;
+        let submsgs = delegate_msgs(&staking, amount, &contract.use_denom);
+        track_withdrawn_rewards(deps.branch(), env, &submsgs, &contract.use_denom)?;
+        rsp = rsp.add_submessages(submsgs);
+    }
+
     // mint token to sender
-    mint_token(deps, &mut rsp, &info.sender, exchange_amount)?;
+    mint_token(deps, env, sender, exchange_amount)?;
 
     InvestedEvent {
         round,  
-        who: &info.sender.as_ref(),
+        who: sender.as_ref(),
         amount,
+        denom: &coin.denom,
     }
     .add_attributes(&mut rsp);
 
     Ok(rsp)
 }
 
-pub fn make_bank_send_msg(addr: String, amount: u128) -> SubMsg {
-    return SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-        to_address: addr,
-        amount: vec![Coin::new(amount, "CONY")],
-    }));
+pub fn make_bank_send_msg(id: u64, addr: String, amount: Coin) -> SubMsg {
+    SubMsg::reply_on_error(
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: addr,
+            amount: vec![amount],
+        }),
+        id,
+    )
+}
+
+pub fn make_cw20_transfer_msg(token: &str, addr: String, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: addr,
+            amount,
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Messages sending `amount` to `addr`, one bank send for the native coins and a transfer
+/// per cw20 token.
+pub(crate) fn make_send_msgs(addr: &str, amount: &[Coin]) -> StdResult<Vec<CosmosMsg>> {
+    let (tokens, native): (Vec<_>, Vec<_>) = amount
+        .iter()
+        .cloned()
+        .partition(|c| c.denom.starts_with(CW20_DENOM_PREFIX));
+    let mut msgs = vec![];
+    if !native.is_empty() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: addr.to_string(),
+                amount: native,
+            }
+            .into(),
+        );
+    }
+    for token in tokens {
+        let contract_addr = &token.denom[CW20_DENOM_PREFIX.len()..];
+        msgs.push(make_cw20_transfer_msg(contract_addr, addr.to_string(), token.amount)?);
+    }
+    Ok(msgs)
+}
+
+/// Payout message by the kind of the denom, a native coin or a cw20 token
+pub fn make_payout_msg(id: u64, addr: String, amount: Coin) -> StdResult<SubMsg> {
+    match amount.denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token) => {
+            let msg = make_cw20_transfer_msg(token, addr, amount.amount)?;
+            Ok(SubMsg::reply_on_error(msg, id))
+        }
+        None => Ok(make_bank_send_msg(id, addr, amount)),
+    }
+}
+
+/// Seed of the draws among investors of a round, from the block that closes it. `denom`
+/// sets apart the draws of the pools.
+///
+/// Every input is public or chosen by the closer, who may pick the block to close in and
+/// so work out the winners beforehand. The draw is only as fair as the operators and the
+/// sudo caller are trusted. Lotto rounds draw from the admin hash chain instead.
+fn draw_seed(env: &Env, round: u32, denom: &str) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(env.block.chain_id.as_bytes())
+        .chain_update(env.block.height.to_be_bytes())
+        .chain_update(env.block.time.nanos().to_be_bytes())
+        .chain_update(round.to_be_bytes())
+        .chain_update(denom.as_bytes())
+        .finalize()
+        .to_vec()
+}
+
+/// Pick one of `entrants` with odds in proportion to its amount. `nonce` tells the picks
+/// made from the same seed apart.
+fn pick_weighted<'a>(entrants: &[&'a Investor], seed: &[u8], nonce: u32) -> Option<&'a Investor> {
+    let total = entrants
+        .iter()
+        .fold(Uint128::zero(), |total, i| total.saturating_add(i.amount));
+    if total.is_zero() {
+        return entrants.first().copied();
+    }
+    let hash = Sha256::new()
+        .chain_update(seed)
+        .chain_update(nonce.to_be_bytes())
+        .finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    let point = u128::from_be_bytes(bytes) % total.u128();
+
+    let mut cumulative = 0u128;
+    entrants.iter().copied().find(|i| {
+        cumulative = cumulative.saturating_add(i.amount.u128());
+        point < cumulative
+    })
+}
+
+/// Pick the winners among `investors`, weighted by their amounts, and split `prize` into
+/// the first winner, second winner and owner shares. The second winner is drawn among the
+/// others, a single investor wins both.
+fn draw_winners(
+    contract: &ContractInfo,
+    investors: &[Investor],
+    prize: Uint128,
+    seed: &[u8],
+) -> Vec<Winner> {
+    let entrants = investors.iter().collect::<Vec<_>>();
+    let first_winner = pick_weighted(&entrants, seed, 0).unwrap_or(&investors[0]);
+    let others = entrants
+        .into_iter()
+        .filter(|i| i.addr != first_winner.addr)
+        .collect::<Vec<_>>();
+    let second_winner = pick_weighted(&others, seed, 1).unwrap_or(first_winner);
+
+    split_prize(contract, &first_winner.addr, &second_winner.addr, prize)
+}
+
+pub fn split_prize(
+    contract: &ContractInfo,
+    first_winner: &str,
+    second_winner: &str,
+    prize: Uint128,
+) -> Vec<Winner> {
+    vec![
+        Winner {
+            addr: first_winner.to_string(),
+            amount: prize.multiply_ratio(contract.first_winner_ratio as u128, 100u128),
+        },
+        Winner {
+            addr: second_winner.to_string(),
+            amount: prize.multiply_ratio(contract.second_winner_ratio as u128, 100u128),
+        },
+        Winner {
+            addr: contract.owner.to_string(),
+            amount: prize.multiply_ratio(contract.owner_ratio as u128, 100u128),
+        },
+    ]
 }
 
 pub fn handle_close_investment(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    // check if owner
+    // check if operator
+    ensure_role(deps.storage, &info.sender, Role::Operator)?;
+    close_round(deps, env)
+}
+
+/// Draw the current round and open the next one. Callers check who may close.
+pub(crate) fn close_round(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
     let contract = CONTRACT_INFO.load(deps.storage)?;
-    if contract.owner != info.sender {
-        return Err(ContractError::Unauthorized {});
-    }
 
     // close investment and add new investment
     let round = CURRENT.load(deps.storage)?.round;
     let mut investment = INVESTMENTS.load(deps.storage, round.to_string())?;
 
-    // drawing winner
-    let investors = INVESTORS
-        .prefix(round.to_string())
-        .range(deps.storage, None, None, Order::Ascending)
-        .filter_map(|item| {
-            item.ok().map(|(addr, amount)| Investor {
-                addr: addr.to_string(),
-                amount,
-            })
-        })
-        .collect::<Vec<_>>();
+    // drawing winner. In no-loss mode every depositor takes part and only the staking
+    // rewards are the prize, otherwise the round's investors share the invested amount.
+    let mut submsgs: Vec<SubMsg> = vec![];
+    let (investors, prize) = match STAKING_CONFIG.may_load(deps.storage)? {
+        Some(staking) => {
+            let investors = PRINCIPALS
+                .range(deps.storage, None, None, Order::Ascending)
+                .filter_map(|item| {
+                    item.ok()
+                        .filter(|(_, amount)| !amount.is_zero())
+                        .map(|(addr, amount)| Investor {
+                            addr: addr.to_string(),
+                            amount,
+                        })
+                })
+                .collect::<Vec<_>>();
+            let (rewards, msgs) =
+                withdraw_reward_msgs(deps.as_ref(), &env, &staking, &contract.use_denom)?;
+            UNDRAWN_REWARDS.remove(deps.storage);
+            submsgs.extend(msgs);
+            (investors, rewards)
+        }
+        None => {
+            let investors = INVESTORS
+                .prefix(round.to_string())
+                .range(deps.storage, None, None, Order::Ascending)
+                .filter_map(|item| {
+                    item.ok().map(|(addr, amount)| Investor {
+                        addr: addr.to_string(),
+                        amount,
+                    })
+                })
+                .collect::<Vec<_>>();
+            (investors, investment.total_amount)
+        }
+    };
+    let prize = prize
+        .checked_add(investment.sponsored_amount)?
+        .checked_add(investment.swept_amount)?;
+
+    // the draws push the owner cut last, it is taken off the payouts
+    let mut drawn = false;
+    let mut payouts = vec![];
+    let mut fee = Uint128::zero();
+    let mut lotto_result = None;
+    if let Some(lotto) = LOTTO_CONFIG.may_load(deps.storage)? {
+        // lotto tiers are paid by matched numbers, the pot may carry a rollover
+        if !investors.is_empty() {
+            let (mut winners, result) = draw_lotto(
+                deps.branch(),
+                &lotto,
+                round,
+                investment.ticket_count,
+                prize,
+            )?;
+            fee = winners.pop().map(|w| w.amount).unwrap_or_default();
+            payouts.extend(winners.into_iter().map(|w| Payout {
+                addr: w.addr,
+                amount: Coin::new(w.amount.u128(), &contract.use_denom),
+            }));
+            lotto_result = Some(result);
+            drawn = true;
+        }
+    } else {
+        // in ticket mode the draw picks ticket ids instead of investors. Bonus weight, and
+        // bonus tickets, may enter players who paid nothing this round.
+        let distribution = match TICKET_CONFIG.may_load(deps.storage)? {
+            Some(_) if investment.ticket_count > 0 => {
+                let seed = draw_seed(&env, round, &contract.use_denom);
+                let (first, second) = draw_tickets(&seed, investment.ticket_count);
+                let first = ticket_owner(deps.as_ref(), round, first)?;
+                let second = ticket_owner(deps.as_ref(), round, second)?;
+                Some(split_prize(&contract, first.as_str(), second.as_str(), prize))
+            }
+            Some(_) => None,
+            None => {
+                let entrants = with_bonus_weights(deps.storage, round, &investors)?;
+                let seed = draw_seed(&env, round, &contract.use_denom);
+                (!entrants.is_empty()).then(|| draw_winners(&contract, &entrants, prize, &seed))
+            }
+        };
+        if let Some(mut distribution) = distribution {
+            fee = distribution.pop().map(|d| d.amount).unwrap_or_default();
+            investment.first_winner = distribution.first().cloned();
+            investment.second_winner = distribution.get(1).cloned();
+            payouts.extend(distribution.into_iter().map(|d| Payout {
+                addr: d.addr,
+                amount: Coin::new(d.amount.u128(), &contract.use_denom),
+            }));
+            drawn = true;
+        }
+    }
+
+    // referrers and LTT holders are paid out of the owner cut, the rest accrues to the fees
+    if drawn {
+        fee = fee.checked_sub(credit_referrals(deps.storage, &investors, fee)?)?;
+        fee = fee.checked_sub(distribute_dividends(deps.storage, fee)?)?;
+        accrue_fee(deps.storage, &contract.use_denom, fee)?;
+    }
 
-    let r_num = 7;
-    let r_num2 = 8;
-    let count = investors.len();
-    // let uuid = new_uuid(&env, deps.storage, deps.api)?;
-    // let hash = Sha256::digest(uuid.as_slice()).to_vec();
-
-    let first_winner = &investors[r_num % count];
-    let second_winner = &investors[r_num2 % count];
-
-    // distribute invest amount
-    let mut distribution = vec![];
-    distribution.push(Winner {
-        addr: first_winner.addr.clone(),
-        amount: investment
-            .total_amount
-            .multiply_ratio(contract.first_winner_ratio as u128, 100u128),
-    });
-    distribution.push(Winner {
-        addr: second_winner.addr.clone(),
-        amount: investment
-            .total_amount
-            .multiply_ratio(contract.second_winner_ratio as u128, 100u128),
-    });
-    distribution.push(Winner {
-        addr: contract.owner.to_string(),
-        amount: investment
-            .total_amount
-            .multiply_ratio(contract.owner_ratio as u128, 100u128),
-    });
+    let pot_drawn = drawn;
 
     // update investment
     investment.in_progress = false;
-    investment.first_winner = distribution.get(0).cloned();
-    investment.second_winner = distribution.get(1).cloned();
+    investment.claims_expire_at = claims_deadline(deps.storage, &env)?;
     INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
 
+    // other accepted denoms are drawn from their own pools
+    let pools = POOLS
+        .prefix(round.to_string())
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (denom, mut pool) in pools {
+        let pool_investors = POOL_INVESTORS
+            .prefix((round.to_string(), denom.as_str()))
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                item.map(|(addr, amount)| Investor {
+                    addr: addr.to_string(),
+                    amount,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        if pool_investors.is_empty() {
+            continue;
+        }
+        drawn = true;
+        let seed = draw_seed(&env, round, &denom);
+        let mut pool_distribution =
+            draw_winners(&contract, &pool_investors, pool.total_amount, &seed);
+        let pool_fee = pool_distribution.pop().map(|d| d.amount).unwrap_or_default();
+        accrue_fee(deps.storage, &denom, pool_fee)?;
+        pool.first_winner = pool_distribution.first().cloned();
+        pool.second_winner = pool_distribution.get(1).cloned();
+        POOLS.save(deps.storage, (round.to_string(), denom.as_str()), &pool)?;
+
+        payouts.extend(pool_distribution.into_iter().map(|d| Payout {
+            addr: d.addr,
+            amount: Coin::new(d.amount.u128(), &denom),
+        }));
+    }
+    if !drawn {
+        return Err(ContractError::NoInvestors { round });
+    }
+
     // update round
     CURRENT.update(deps.storage, |c| -> StdResult<_> {
         Ok(Current {
@@ -248,32 +889,254 @@ pub fn handle_close_investment(
         })
     })?;
     // create new investment & save
-    let new_investment = Investment::new(round + 1);
+    let mut new_investment = next_investment(deps.storage, &env, round + 1)?;
+    // only the pools were drawn, the sponsorships and swept claims go to the next prize
+    if !pot_drawn {
+        new_investment.sponsored_amount = investment.sponsored_amount;
+        new_investment.swept_amount = investment.swept_amount;
+    }
+    let reinvested = reinvest_winnings(
+        deps.branch(),
+        &env,
+        &contract,
+        &mut new_investment,
+        &mut payouts,
+    )?;
     INVESTMENTS.save(deps.storage, (round + 1).to_string(), &new_investment)?;
-
-    // distribute prize
-    let mut submsgs: Vec<SubMsg> = vec![];
-    for d in distribution {
-        submsgs.push(make_bank_send_msg(d.addr, d.amount.u128()));
+    // a large first prize is released over time instead of sent at once
+    let top_prizes = match (&lotto_result, &investment.first_winner) {
+        (Some(result), _) => top_tier_winners(result),
+        (None, Some(_)) => 0..1,
+        (None, None) => 0..0,
+    };
+    let mut vested = Uint128::zero();
+    for payout in payouts[top_prizes].iter_mut() {
+        vested = vested.checked_add(vest_prize(deps.storage, &env, round, payout)?)?;
     }
+    // the first batch of subscribers enters the new round, ProcessSubscriptions the rest
+    let (entered, _) = enter_subscribers(deps.branch(), &env, SUBSCRIPTION_BATCH)?;
+
+    // distribute prize. A failed send is moved to the claimable balance in `reply`, so
+    // a bad recipient does not revert the close.
+    payouts.retain(|p| !p.amount.amount.is_zero());
+    for (i, p) in payouts.iter().enumerate() {
+        let id = PAYOUT_REPLY_ID + i as u64;
+        submsgs.push(make_payout_msg(id, p.addr.clone(), p.amount.clone())?);
+    }
+    PENDING_PAYOUTS.save(deps.storage, &payouts)?;
 
-    // staking
-
+    let winner_addr = |w: &Option<Winner>| w.as_ref().map(|w| w.addr.clone()).unwrap_or_default();
     let closed_investment_event = ClosedInvestmentEvent {
         round,
-        first_winner: first_winner.addr.as_str(),
-        second_winner: &second_winner.addr.as_str(),
+        first_winner: &winner_addr(&investment.first_winner),
+        second_winner: &winner_addr(&investment.second_winner),
         winner_hash: "",
+        reinvested,
+        vested,
+        subscribers_entered: entered,
     };
 
     let mut res = Response::new().add_submessages(submsgs);
     closed_investment_event.add_attributes(&mut res);
+    if let Some(result) = lotto_result {
+        LottoDrawnEvent {
+            round,
+            numbers: &result.numbers,
+            rollover: result.rollover,
+        }
+        .add_attributes(&mut res);
+    }
 
     Ok(res)
 }
 
+/// A fresh round, due `ROUND_DURATION` after `env` when the contract has one.
+pub(crate) fn next_investment(
+    storage: &dyn Storage,
+    env: &Env,
+    round: u32,
+) -> StdResult<Investment> {
+    let mut investment = Investment::new(round);
+    investment.deadline = ROUND_DURATION
+        .may_load(storage)?
+        .map(|d| env.block.time.plus_seconds(d));
+    Ok(investment)
+}
+
+/// Whether rounds take plain investments, as opposed to tickets, lotto tickets or
+/// no-loss deposits.
+pub(crate) fn is_invest_mode(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(TICKET_CONFIG.may_load(storage)?.is_none()
+        && LOTTO_CONFIG.may_load(storage)?.is_none()
+        && STAKING_CONFIG.may_load(storage)?.is_none())
+}
+
+/// Enter `addr` in `investment` with `amount` of `use_denom` paid earlier, for entries the
+/// contract makes on the player's behalf. Nothing is entered, and false returned, when the
+/// player may not play the amount now, e.g. over a limit or self-excluded. A `prepaid`
+/// amount was counted against the deposit limits when it was paid.
+pub(crate) fn enter_on_behalf(
+    deps: DepsMut,
+    env: &Env,
+    contract: &ContractInfo,
+    investment: &mut Investment,
+    addr: &Addr,
+    amount: Uint128,
+    prepaid: bool,
+) -> Result<bool, ContractError> {
+    let deposit = if prepaid { Uint128::zero() } else { amount };
+    if ensure_allowed(deps.storage, addr).is_err()
+        || ensure_invest_limits(deps.storage, investment, addr, amount).is_err()
+        || record_deposit(deps.storage, env, addr, deposit).is_err()
+    {
+        return Ok(false);
+    }
+    bind_referrer(deps.storage, deps.api, addr, None)?;
+
+    investment.total_amount = investment.total_amount.checked_add(amount)?;
+    INVESTORS.update(
+        deps.storage,
+        (investment.round.to_string(), addr),
+        |a| -> StdResult<_> { Ok(a.unwrap_or_default().checked_add(amount)?) },
+    )?;
+    let exchange_amount = amount
+        .checked_mul(Uint128::new(contract.exchange_ratio))
+        .map_err(|e| ContractError::CustomError { val: e.to_string() })?;
+    mint_token(deps, env, addr, exchange_amount)?;
+    Ok(true)
+}
+
+fn handle_instantiate_ticket_nft_reply(
+    deps: DepsMut,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let collection = deps.api.addr_validate(&parse_instantiated_address(msg)?)?;
+    TICKET_NFT.save(deps.storage, &collection)?;
+
+    let mut rsp = Response::new();
+    TicketNftInstantiatedEvent {
+        collection: collection.as_str(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+// a payout reply id is bounded by the payouts sent by the last close
+fn is_payout_reply(storage: &dyn Storage, id: u64) -> StdResult<bool> {
+    if id < PAYOUT_REPLY_ID {
+        return Ok(false);
+    }
+    let pending = PENDING_PAYOUTS.may_load(storage)?.unwrap_or_default();
+    Ok(id - PAYOUT_REPLY_ID < pending.len() as u64)
+}
+
+fn handle_payout_reply(
+    deps: DepsMut,
+    index: u64,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let reason = match result {
+        SubMsgResult::Err(reason) => reason,
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+    };
+    let payout = PENDING_PAYOUTS.load(deps.storage)?[index as usize].clone();
+
+    // payouts are sent by the close of the round before the current one
+    let round = CURRENT.load(deps.storage)?.round - 1;
+    let addr = deps.api.addr_validate(&payout.addr)?;
+    credit_claim(
+        deps.storage,
+        round,
+        &addr,
+        payout.amount.denom.as_str(),
+        payout.amount.amount,
+    )?;
+
+    let mut rsp = Response::new();
+    PayoutFailedEvent {
+        who: payout.addr.as_str(),
+        amount: &payout.amount,
+        reason: reason.as_str(),
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+pub fn handle_claim_prize(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let amount = CLAIMABLE
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin::new(amount.u128(), denom)))
+        .collect::<StdResult<Vec<_>>>()?;
+    if amount.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    for coin in amount.iter() {
+        CLAIMABLE.remove(deps.storage, (&info.sender, coin.denom.as_str()));
+    }
+    forget_claims(deps.storage, &info.sender)?;
+
+    let mut rsp = Response::new().add_messages(make_send_msgs(info.sender.as_str(), &amount)?);
+    PrizeClaimedEvent {
+        who: info.sender.as_str(),
+        amount: &amount,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+pub fn handle_add_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    exchange_ratio: u128,
+) -> Result<Response, ContractError> {
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    // the investment limits would not apply to the pool of the denom
+    if INVEST_LIMITS.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::InvalidParams {});
+    }
+    validate_denom(deps.as_ref(), &contract, &denom)?;
+    ACCEPTED_DENOMS.save(deps.storage, &denom, &exchange_ratio)?;
+
+    let mut rsp = Response::new();
+    DenomUpdatedEvent {
+        denom: &denom,
+        exchange_ratio: Some(exchange_ratio),
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+pub fn handle_remove_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if !ACCEPTED_DENOMS.has(deps.storage, &denom) {
+        return Err(ContractError::ExtraDenom(denom));
+    }
+    // a pool already opened in the current round is still drawn at close
+    ACCEPTED_DENOMS.remove(deps.storage, &denom);
+
+    let mut rsp = Response::new();
+    DenomUpdatedEvent {
+        denom: &denom,
+        exchange_ratio: None,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
 pub fn handl_transfer_token(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     to: String,
     amount: Uint128,
@@ -283,22 +1146,26 @@ pub fn handl_transfer_token(
     }
 
     let to_addr = deps.api.addr_validate(&to)?;
+    ensure_allowed(deps.storage, &info.sender)?;
+    ensure_allowed(deps.storage, &to_addr)?;
 
-    BALANCES.update(deps.storage, &info.sender, |balance| -> StdResult<_> {
+    settle_dividends(deps.storage, &info.sender)?;
+    settle_dividends(deps.storage, &to_addr)?;
+    let height = env.block.height;
+    BALANCES.update(deps.storage, &info.sender, height, |balance| -> StdResult<_> {
         Ok(balance.unwrap_or_default().checked_sub(amount)?)
     })?;
-    BALANCES.update(deps.storage, &to_addr, |balance| -> StdResult<_> {
+    BALANCES.update(deps.storage, &to_addr, height, |balance| -> StdResult<_> {
         Ok(balance.unwrap_or_default().checked_add(amount)?)
     })?;
 
-    let rsp = Response::new();
-
-    // TokenTransferredEvent {
-    //     from: info.sender.as_ref(),
-    //     to: to.as_ref(),
-    //     amount,
-    // }
-    // .add_attributes(&mut rsp);
+    let mut rsp = Response::new();
+    TokenTransferredEvent {
+        from: info.sender.as_ref(),
+        to: to_addr.as_ref(),
+        amount,
+    }
+    .add_attributes(&mut rsp);
 
     Ok(rsp)
 }
@@ -306,13 +1173,29 @@ pub fn handl_transfer_token(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::FsLotteryContract;
     use crate::msg::{InfoResponse, QueryMsg};
+    use crate::lotto::{draw_numbers, round_seed};
+    use crate::nft::{mint_ticket_msg, Cw721QueryMsg, OwnerOfResponse};
+    use crate::responsible::LOOSENING_DELAY;
+    use crate::scratch::{scratch_multiplier, SCRATCH_REVEAL_TIMEOUT};
+    use crate::staking::{drop_inactive_validators, UNBOND_EPOCHS};
     use crate::queries::*;
-    use crate::state::Investor;
-    use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary, StdError};
-
-    #[test]
+    use crate::state::{
+        ClaimExpiry, InvestLimits, Investor, LottoConfig, LottoTier, OverpaymentPolicy,
+        PausableOperation, ScratchConfig, ScratchPrize, StakingConfig, SweepTarget, TicketConfig,
+        TicketNftConfig, VestingConfig, WeightedValidator,
+    };
+    use cosmwasm_std::testing::{
+        mock_dependencies_with_balance, mock_env, mock_info, MOCK_CONTRACT_ADDR,
+    };
+    use sha2::{Digest, Sha256};
+    use cosmwasm_std::{
+        coins, Binary, ContractResult, Decimal, FullDelegation, ReplyOn, StakingMsg, StdError,
+        SubMsgResponse, SystemError, SystemResult, Timestamp, Validator, WasmQuery,
+    };
+
+    #[test]
     fn proper_initialization() {
         let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
 
@@ -326,6 +1209,7 @@ mod tests {
             token_name: "lottery".to_string(),
             token_symbol: "LTT".to_string(),
             token_decimals: 6u8,
+            ..default_instantiate_msg()
         };
         let info: MessageInfo = mock_info("creator", &coins(1000, "cony"));
 
@@ -339,8 +1223,8 @@ mod tests {
         assert_eq!(10, value.exchange_ratio);
     }
 
-    fn do_instantiate(deps: DepsMut, info: MessageInfo) {
-        let msg = InstantiateMsg {
+    fn default_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
             use_denom: "cony".to_string(),
             exchange_ratio: 10,
             min_exchange_amount: 200000000u32,
@@ -350,7 +1234,26 @@ mod tests {
             token_name: "lottery".to_string(),
             token_symbol: "LTT".to_string(),
             token_decimals: 6u8,
-        };
+            no_loss: None,
+            accepted_denoms: None,
+            tickets: None,
+            ticket_nft: None,
+            lotto: None,
+            scratch: None,
+            round_duration: None,
+            limits: None,
+            referral_share: None,
+            vesting: None,
+            claim_expiry: None,
+            dividend_share: None,
+            redeem_rate: None,
+            owner: None,
+            factory: None,
+        }
+    }
+
+    fn do_instantiate(deps: DepsMut, info: MessageInfo) {
+        let msg = default_instantiate_msg();
 
         // we can just call .unwrap() to assert this was a success
         let res = instantiate(deps, mock_env(), info, msg).unwrap();
@@ -359,10 +1262,19 @@ mod tests {
 
     fn do_invest(deps: DepsMut, addr: &str, amount: u128) {
         let auth_info = mock_info(addr, &coins(amount, "cony"));
-        let msg = ExecuteMsg::Invest {};
+        let msg = ExecuteMsg::Invest { referrer: None };
         execute(deps, mock_env(), auth_info, msg).unwrap();
     }
 
+    // value of the first attribute named `key`
+    fn attribute<'a>(res: &'a Response, key: &str) -> &'a str {
+        res.attributes
+            .iter()
+            .find(|a| a.key == key)
+            .map(|a| a.value.as_str())
+            .unwrap_or_else(|| panic!("no attribute {}", key))
+    }
+
     #[test]
     fn invest() {
         let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
@@ -396,6 +1308,105 @@ mod tests {
         assert_eq!(Uint128::new(10000), res.balance);
     }
 
+    #[test]
+    fn invest_limits() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let limits = InvestLimits {
+            min_invest: Some(Uint128::new(100)),
+            max_invest_per_address_per_round: Some(Uint128::new(1000)),
+            max_pot: Some(Uint128::new(1500)),
+            overflow: OverpaymentPolicy::Reject,
+        };
+        let msg = InstantiateMsg {
+            limits: Some(limits.clone()),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let invest = |deps: DepsMut, addr: &str, amount: u128| {
+            let info = mock_info(addr, &coins(amount, "cony"));
+            execute(deps, mock_env(), info, ExecuteMsg::Invest { referrer: None })
+        };
+        let err = invest(deps.as_mut(), "alpha", 99).unwrap_err();
+        assert!(matches!(err, ContractError::BelowMinInvest { .. }));
+        invest(deps.as_mut(), "alpha", 800).unwrap();
+        let err = invest(deps.as_mut(), "alpha", 300).unwrap_err();
+        assert_eq!(
+            "Over the per-address cap: available Uint128(200), received Uint128(300)",
+            err.to_string()
+        );
+        invest(deps.as_mut(), "beta", 600).unwrap();
+        let err = invest(deps.as_mut(), "gamma", 200).unwrap_err();
+        assert!(matches!(err, ContractError::PotCapExceeded { .. }));
+
+        // with the refund policy the round takes what fits under the caps
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            limits: Some(InvestLimits {
+                overflow: OverpaymentPolicy::Refund,
+                ..limits
+            }),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let res = invest(deps.as_mut(), "alpha", 1200).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alpha".to_string(),
+                amount: coins(200, "cony"),
+            }),
+            res.messages[0].msg
+        );
+        let res = invest(deps.as_mut(), "beta", 600).unwrap();
+        assert_eq!(coins(100, "cony"), match &res.messages[0].msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount.clone(),
+            msg => panic!("unexpected message {:?}", msg),
+        });
+        let res = query_current_investment(deps.as_ref()).unwrap();
+        assert_eq!(Uint128::new(1500), res.total_amount);
+        let res = query_token_balance(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(10000), res.balance);
+
+        // the pot is full, nothing is left to accept
+        let err = invest(deps.as_mut(), "gamma", 100).unwrap_err();
+        assert!(matches!(err, ContractError::PotCapExceeded { .. }));
+
+        // the limits do not cap the pools of other denoms, those are not accepted
+        let msg = ExecuteMsg::AddDenom {
+            denom: "brown".to_string(),
+            exchange_ratio: 2,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidParams {}));
+
+        // tickets cannot be split, an order over a cap fails whatever the policy
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            tickets: Some(TicketConfig {
+                price: Uint128::new(100),
+                overpayment: OverpaymentPolicy::Refund,
+            }),
+            limits: Some(InvestLimits {
+                overflow: OverpaymentPolicy::Refund,
+                ..limits
+            }),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let buy = |deps: DepsMut, count: u64| {
+            let info = mock_info("alpha", &coins(count as u128 * 100, "cony"));
+            let msg = ExecuteMsg::BuyTickets {
+                count,
+                referrer: None,
+            };
+            execute(deps, mock_env(), info, msg)
+        };
+        buy(deps.as_mut(), 8).unwrap();
+        let err = buy(deps.as_mut(), 3).unwrap_err();
+        assert!(matches!(err, ContractError::AddressCapExceeded { .. }));
+        buy(deps.as_mut(), 2).unwrap();
+    }
+
     #[test]
     fn close_investment() {
         let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
@@ -427,6 +1438,773 @@ mod tests {
         assert_eq!(4, res.investors.len());
     }
 
+    #[test]
+    fn buy_tickets() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+
+        let msg = InstantiateMsg {
+            tickets: Some(TicketConfig {
+                price: Uint128::new(100),
+                overpayment: OverpaymentPolicy::Refund,
+            }),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        // free-form investments are not accepted
+        let info = mock_info("alpha", &coins(100, "cony"));
+        let msg = ExecuteMsg::Invest { referrer: None };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::TicketsOnly {}));
+
+        let info = mock_info("alpha", &coins(250, "cony"));
+        let msg = ExecuteMsg::BuyTickets {
+            count: 3,
+            referrer: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPayment { .. }));
+
+        // the overpayment is refunded
+        let info = mock_info("alpha", &coins(250, "cony"));
+        let msg = ExecuteMsg::BuyTickets {
+            count: 2,
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alpha".to_string(),
+                amount: coins(50, "cony"),
+            })],
+            res.messages
+        );
+        let info = mock_info("beta", &coins(800, "cony"));
+        let msg = ExecuteMsg::BuyTickets {
+            count: 8,
+            referrer: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query_tickets(deps.as_ref(), 1, "alpha".to_string(), None, None).unwrap();
+        assert_eq!(vec![1, 2], res.tickets);
+        let res = query_tickets(deps.as_ref(), 1, "beta".to_string(), Some(8), None).unwrap();
+        assert_eq!(vec![9, 10], res.tickets);
+        let res = query_current_investment(deps.as_ref()).unwrap();
+        assert_eq!(Uint128::new(1000), res.total_amount);
+
+        // the draw picks ticket ids from the seed of the closing block
+        let (first, _) = draw_tickets(&draw_seed(&mock_env(), 1, "cony"), 10);
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let res = query_invest_result(deps.as_ref(), 1).unwrap();
+        let owner = if first <= 2 { "alpha" } else { "beta" };
+        assert_eq!(owner, res.first_winner.addr);
+        assert_eq!(Uint128::new(600), res.first_winner.amount);
+    }
+
+    #[test]
+    fn ticket_draw() {
+        // distinct ids in 1..=count, which follow the seed
+        let mut firsts = vec![0u32; 10];
+        for n in 0u32..1000 {
+            let (first, second) = draw_tickets(&n.to_be_bytes(), 10);
+            assert!((1..=10).contains(&first) && (1..=10).contains(&second));
+            assert_ne!(first, second);
+            firsts[first as usize - 1] += 1;
+        }
+        assert!(firsts.iter().all(|n| (50..150).contains(n)), "{:?}", firsts);
+        assert_eq!((1, 1), draw_tickets(&[0u8], 1));
+
+        let mut env = mock_env();
+        let draws = (0..10)
+            .map(|_| {
+                env.block.height += 1;
+                draw_tickets(&draw_seed(&env, 1, "cony"), 1000)
+            })
+            .collect::<Vec<_>>();
+        assert!(draws[1..].iter().any(|d| *d != draws[0]), "{:?}", draws);
+    }
+
+    #[test]
+    fn ticket_nfts() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+
+        let msg = InstantiateMsg {
+            tickets: Some(TicketConfig {
+                price: Uint128::new(100),
+                overpayment: OverpaymentPolicy::Reject,
+            }),
+            ticket_nft: Some(TicketNftConfig {
+                code_id: 7,
+                name: "lottery tickets".to_string(),
+                symbol: "TCK".to_string(),
+            }),
+            ..default_instantiate_msg()
+        };
+        let res = instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(INSTANTIATE_TICKET_NFT_REPLY_ID, res.messages[0].id);
+
+        // the collection address comes back through the reply
+        let reply_msg = Reply {
+            id: INSTANTIATE_TICKET_NFT_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![cosmwasm_std::Event::new("instantiate")
+                    .add_attribute("_contract_address", "nft")],
+                data: None,
+            }),
+        };
+        reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Info {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Some("nft".to_string()), value.ticket_nft);
+
+        let info = mock_info("alpha", &coins(1000, "cony"));
+        let msg = ExecuteMsg::BuyTickets {
+            count: 10,
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(10, res.messages.len());
+        assert_eq!(
+            SubMsg::new(
+                mint_ticket_msg(&Addr::unchecked("nft"), &Addr::unchecked("alpha"), 1, 1).unwrap()
+            ),
+            res.messages[0]
+        );
+
+        // the first drawn ticket was handed over, the prize goes to the holder of the NFT
+        // the second one was burned, it stays with its buyer
+        let (first, second) = draw_tickets(&draw_seed(&mock_env(), 1, "cony"), 10);
+        let (handed_over, burned) = (format!("1-{}", first), format!("1-{}", second));
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "nft" => {
+                let Cw721QueryMsg::OwnerOf { token_id, .. } = from_binary(msg).unwrap();
+                if token_id == burned {
+                    return SystemResult::Ok(ContractResult::Err("token not found".to_string()));
+                }
+                let owner = if token_id == handed_over { "gamma" } else { "alpha" };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&OwnerOfResponse {
+                        owner: owner.to_string(),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let res = query_invest_result(deps.as_ref(), 1).unwrap();
+        assert_eq!("gamma", res.first_winner.addr);
+        assert_eq!("alpha", res.second_winner.addr);
+
+        assert_eq!("alpha", ticket_owner(deps.as_ref(), 1, second).unwrap());
+        assert_eq!("gamma", ticket_owner(deps.as_ref(), 1, first).unwrap());
+
+        // other failures are not taken for a burned ticket
+        deps.querier.update_wasm(|_| {
+            SystemResult::Ok(ContractResult::Err("out of gas".to_string()))
+        });
+        ticket_owner(deps.as_ref(), 1, first).unwrap_err();
+        deps.querier.update_wasm(|_| {
+            SystemResult::Err(SystemError::NoSuchContract {
+                addr: "nft".to_string(),
+            })
+        });
+        ticket_owner(deps.as_ref(), 1, first).unwrap_err();
+    }
+
+    #[test]
+    fn lotto_round() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+
+        let msg = InstantiateMsg {
+            lotto: Some(LottoConfig {
+                pick: 3,
+                max_number: 10,
+                price: Uint128::new(100),
+                tiers: vec![
+                    LottoTier {
+                        matches: 3,
+                        ratio: 50,
+                    },
+                    LottoTier {
+                        matches: 2,
+                        ratio: 30,
+                    },
+                ],
+            }),
+            vesting: Some(VestingConfig {
+                threshold: Uint128::new(100),
+                duration: 1000,
+            }),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        let buy = |deps: DepsMut, who: &str, numbers: Vec<u8>| {
+            let info = mock_info(who, &coins(100, "cony"));
+            execute(deps, mock_env(), info, ExecuteMsg::BuyLottoTicket {
+                numbers,
+                referrer: None,
+            })
+        };
+        let err = buy(deps.as_mut(), "alpha", vec![1, 1, 2]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidNumbers {}));
+        let err = buy(deps.as_mut(), "alpha", vec![1, 2, 11]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidNumbers {}));
+
+        // chain of secret <- link1 <- anchor, one seed per round
+        let secret = Binary::from(vec![7u8; 32]);
+        let link1 = Binary::from(Sha256::digest(secret.as_slice()).to_vec());
+        let anchor = Binary::from(Sha256::digest(link1.as_slice()).to_vec());
+        let msg = ExecuteMsg::CommitLottoChain { anchor, length: 2 };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        // a round without tickets keeps selling, it would never be drawn
+        let msg = ExecuteMsg::RevealLottoSeed {
+            preimage: link1.clone(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoInvestors { round: 1 }));
+
+        // the test knows the chain, so it knows the numbers of a round closing with 3 tickets
+        let winning = draw_numbers(&round_seed(&link1, 1, 3), 3, 10);
+        let losing = (1..=10u8)
+            .filter(|n| !winning.contains(n))
+            .take(3)
+            .collect::<Vec<_>>();
+        buy(deps.as_mut(), "alpha", winning.clone()).unwrap();
+        buy(deps.as_mut(), "beta", vec![winning[0], winning[1], losing[0]]).unwrap();
+        buy(deps.as_mut(), "gamma", losing.clone()).unwrap();
+
+        // the round is drawn from its revealed seed, which ends the sales
+        let msg = ExecuteMsg::CloseInvestment {};
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::LottoSeedNotRevealed { round: 1 }));
+        let msg = ExecuteMsg::RevealLottoSeed {
+            preimage: secret.clone(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPreimage {}));
+        let msg = ExecuteMsg::RevealLottoSeed { preimage: link1 };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let err = buy(deps.as_mut(), "delta", losing).unwrap_err();
+        assert!(matches!(err, ContractError::LottoSalesClosed { round: 1 }));
+
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let result = query_lotto_result(deps.as_ref(), 1).unwrap();
+        assert_eq!(winning, result.numbers);
+        assert_eq!(Uint128::new(150), result.tiers[0].prize);
+        assert_eq!(Uint128::new(90), result.tiers[1].prize);
+        // 300 - 150 - 90 - 6 for the owner
+        assert_eq!(Uint128::new(54), result.rollover);
+
+        // the jackpot is vested like a first prize, the lower tier is sent
+        assert_eq!("150", attribute(&res, "vested"));
+        assert_eq!(
+            vec![SubMsg::reply_on_error(
+                BankMsg::Send {
+                    to_address: "beta".to_string(),
+                    amount: coins(90, "cony"),
+                },
+                PAYOUT_REPLY_ID
+            )],
+            res.messages
+        );
+        let res = query_vesting(deps.as_ref(), mock_env(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(150), res.schedules[0].total);
+
+        // without a jackpot winner its share rolls over again
+        let winning = draw_numbers(&round_seed(&secret, 2, 1), 3, 10);
+        let losing = (1..=10u8)
+            .filter(|n| !winning.contains(n))
+            .take(3)
+            .collect::<Vec<_>>();
+        buy(deps.as_mut(), "alpha", losing).unwrap();
+        let msg = ExecuteMsg::RevealLottoSeed { preimage: secret };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let res = query_lotto_result(deps.as_ref(), 2).unwrap();
+        assert_eq!(0, res.tiers[0].winners);
+        assert_eq!(Uint128::new(152), res.rollover);
+    }
+
+    #[test]
+    fn scratch_cards() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+
+        let config = ScratchConfig {
+            price: Uint128::new(10),
+            prizes: vec![ScratchPrize {
+                odds: 2,
+                multiplier: 3,
+            }],
+        };
+        let mut msg = default_instantiate_msg();
+        msg.scratch = Some(config.clone());
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        // no chain committed yet
+        let player = mock_info("alpha", &coins(10, "cony"));
+        let err = execute(deps.as_mut(), mock_env(), player.clone(), ExecuteMsg::Scratch {})
+            .unwrap_err();
+        assert!(matches!(err, ContractError::ScratchChainExhausted {}));
+
+        // chain of secret <- link1 <- link2 <- anchor
+        let secret = Binary::from(vec![1u8; 32]);
+        let link1 = Binary::from(Sha256::digest(secret.as_slice()).to_vec());
+        let link2 = Binary::from(Sha256::digest(link1.as_slice()).to_vec());
+        let anchor = Binary::from(Sha256::digest(link2.as_slice()).to_vec());
+        let msg = ExecuteMsg::CommitScratchChain {
+            anchor: anchor.clone(),
+            length: 3,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg.clone())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::MissingRole {
+            role: Role::Admin
+        }));
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg.clone()).unwrap();
+
+        // the top prize is not covered yet
+        let err = execute(deps.as_mut(), mock_env(), player.clone(), ExecuteMsg::Scratch {})
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientBankroll { .. }));
+        let info = mock_info("creator", &coins(100, "cony"));
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::FundBankroll {}).unwrap();
+
+        // plays are recorded first, nothing is paid before the reveal
+        for play in 1..=3u64 {
+            let res =
+                execute(deps.as_mut(), mock_env(), player.clone(), ExecuteMsg::Scratch {}).unwrap();
+            assert!(res.messages.is_empty());
+            assert_eq!(play.to_string(), attribute(&res, "play"));
+        }
+        let err = execute(deps.as_mut(), mock_env(), player.clone(), ExecuteMsg::Scratch {})
+            .unwrap_err();
+        assert!(matches!(err, ContractError::ScratchChainExhausted {}));
+        let res = query_scratch_info(deps.as_ref()).unwrap();
+        assert_eq!((3, 3, 0), (res.plays, res.pending, res.remaining));
+        // the chain cannot be swapped under the plays, nor the bankroll they need withdrawn
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::ScratchPlaysPending {}));
+        let msg = ExecuteMsg::WithdrawBankroll {
+            amount: Uint128::new(50),
+        };
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientBankroll { .. }));
+
+        let msg = ExecuteMsg::RevealScratch {
+            preimages: vec![link1.clone()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPreimage {}));
+
+        // every revealed link settles the next play
+        let mut bankroll = Uint128::new(130);
+        for (play, preimage) in [link2, link1, secret].iter().enumerate() {
+            let play = play as u64 + 1;
+            let multiplier = scratch_multiplier(&config, preimage, "alpha", play);
+            let prize = Uint128::new(10 * multiplier as u128);
+            bankroll -= prize;
+
+            let msg = ExecuteMsg::RevealScratch {
+                preimages: vec![preimage.clone()],
+            };
+            let res = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+            assert_eq!(multiplier.to_string(), attribute(&res, "multiplier"));
+            if multiplier == 0 {
+                assert!(res.messages.is_empty());
+            } else {
+                assert_eq!(
+                    vec![SubMsg::new(BankMsg::Send {
+                        to_address: "alpha".to_string(),
+                        amount: coins(prize.u128(), "cony"),
+                    })],
+                    res.messages
+                );
+            }
+        }
+        let res = query_scratch_info(deps.as_ref()).unwrap();
+        assert_eq!(bankroll, res.bankroll);
+        assert_eq!((3, 0), (res.plays, res.pending));
+        let msg = ExecuteMsg::RevealScratch {
+            preimages: vec![Binary::from(vec![0u8; 32])],
+        };
+        let err = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidParams {}));
+    }
+
+    #[test]
+    fn scratch_refund() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        let mut msg = default_instantiate_msg();
+        msg.scratch = Some(ScratchConfig {
+            price: Uint128::new(10),
+            prizes: vec![ScratchPrize {
+                odds: 2,
+                multiplier: 3,
+            }],
+        });
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        let secret = Binary::from(vec![1u8; 32]);
+        let link1 = Binary::from(Sha256::digest(secret.as_slice()).to_vec());
+        let anchor = Binary::from(Sha256::digest(link1.as_slice()).to_vec());
+        let msg = ExecuteMsg::CommitScratchChain { anchor, length: 3 };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let info = mock_info("creator", &coins(100, "cony"));
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::FundBankroll {}).unwrap();
+
+        let refund = |deps: DepsMut, env: Env| {
+            execute(deps, env, mock_info("anyone", &[]), ExecuteMsg::RefundScratch {})
+        };
+        let mut env = mock_env();
+        let err = refund(deps.as_mut(), env.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::ScratchRevealNotDue {}));
+        for who in ["alpha", "beta"] {
+            let info = mock_info(who, &coins(10, "cony"));
+            execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Scratch {}).unwrap();
+        }
+
+        // the plays wait for the owner until the timeout, then anyone may refund them
+        env.block.time = env.block.time.plus_seconds(SCRATCH_REVEAL_TIMEOUT - 1);
+        let err = refund(deps.as_mut(), env.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::ScratchRevealNotDue {}));
+        env.block.time = env.block.time.plus_seconds(1);
+        let res = refund(deps.as_mut(), env.clone()).unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "alpha".to_string(),
+                    amount: coins(10, "cony"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "beta".to_string(),
+                    amount: coins(10, "cony"),
+                }),
+            ],
+            res.messages
+        );
+        assert_eq!("ScratchRefunded", attribute(&res, "action"));
+        let res = query_scratch_info(deps.as_ref()).unwrap();
+        assert_eq!(Uint128::new(100), res.bankroll);
+        assert_eq!((2, 0, 0), (res.plays, res.pending, res.remaining));
+
+        // the chain is given up, a new one takes the next plays
+        let msg = ExecuteMsg::RevealScratch {
+            preimages: vec![link1],
+        };
+        let err = execute(deps.as_mut(), env.clone(), auth_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidParams {}));
+        let info = mock_info("alpha", &coins(10, "cony"));
+        let err = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Scratch {}).unwrap_err();
+        assert!(matches!(err, ContractError::ScratchChainExhausted {}));
+        let msg = ExecuteMsg::CommitScratchChain {
+            anchor: Binary::from(Sha256::digest(secret.as_slice()).to_vec()),
+            length: 1,
+        };
+        execute(deps.as_mut(), env, auth_info, msg).unwrap();
+    }
+
+    #[test]
+    fn factory() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        do_instantiate(deps.as_mut(), auth_info.clone());
+
+        // templates need factory mode and name the owner of their lotteries
+        let template = default_instantiate_msg();
+        let msg = ExecuteMsg::AddTemplate {
+            name: "daily".to_string(),
+            code_id: 5,
+            msg: Box::new(template.clone()),
+        };
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::FactoryDisabled {}));
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            factory: Some(true),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let add = |template: &InstantiateMsg| ExecuteMsg::AddTemplate {
+            name: "daily".to_string(),
+            code_id: 5,
+            msg: Box::new(template.clone()),
+        };
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), add(&template))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::TemplateWithoutOwner {}));
+        let template = InstantiateMsg {
+            owner: Some("treasury".to_string()),
+            ..template
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), add(&template))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::MissingRole {
+            role: Role::Admin
+        }));
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), add(&template)).unwrap();
+
+        let create = |label: &str| ExecuteMsg::CreateLottery {
+            template: "daily".to_string(),
+            label: label.to_string(),
+        };
+        for (label, addr) in [("daily-1", "lottery1"), ("daily-2", "lottery2")] {
+            let res = execute(deps.as_mut(), mock_env(), auth_info.clone(), create(label)).unwrap();
+            let instantiate_msg = FsLotteryContract::instantiate(
+                5,
+                &template,
+                label.to_string(),
+                Some("treasury".to_string()),
+            )
+            .unwrap();
+            assert_eq!(
+                vec![SubMsg::reply_on_success(instantiate_msg, INSTANTIATE_LOTTERY_REPLY_ID)],
+                res.messages
+            );
+
+            let reply_msg = Reply {
+                id: INSTANTIATE_LOTTERY_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![cosmwasm_std::Event::new("instantiate")
+                        .add_attribute("_contract_address", addr)],
+                    data: None,
+                }),
+            };
+            reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        }
+
+        let res = query_lotteries(deps.as_ref(), None, Some(1)).unwrap();
+        assert_eq!("lottery1", res.lotteries[0].address);
+        assert_eq!("daily-1", res.lotteries[0].label);
+        let res = query_lotteries(deps.as_ref(), Some("lottery1".to_string()), None).unwrap();
+        assert_eq!(1, res.lotteries.len());
+        assert_eq!("lottery2", res.lotteries[0].address);
+
+        let msg = ExecuteMsg::RemoveTemplate {
+            name: "daily".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), create("daily-3"))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownTemplate { .. }));
+
+        // owner actions are forwarded to every registered lottery
+        let action = ExecuteMsg::AddDenom {
+            denom: "brown".to_string(),
+            exchange_ratio: 2,
+        };
+        let msg = ExecuteMsg::ExecuteOnLotteries {
+            msg: Box::new(action.clone()),
+            start_after: None,
+            limit: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let expected = ["lottery1", "lottery2"]
+            .iter()
+            .map(|addr| {
+                let lottery = FsLotteryContract(Addr::unchecked(*addr));
+                SubMsg::new(lottery.call(action.clone()).unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, res.messages);
+
+        // an instance belongs to the template owner, the factory keeps the roles to manage it
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, template).unwrap();
+        let res = query_roles(deps.as_ref()).unwrap();
+        assert_eq!("treasury", res.owner);
+        for members in res.roles {
+            let expected = match members.role {
+                Role::Treasurer => vec![],
+                _ => vec![MOCK_CONTRACT_ADDR.to_string()],
+            };
+            assert_eq!(expected, members.members);
+        }
+    }
+
+    #[test]
+    fn pools_per_denom() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+
+        do_instantiate(deps.as_mut(), auth_info.clone());
+        let msg = ExecuteMsg::AddDenom {
+            denom: "brown".to_string(),
+            exchange_ratio: 2,
+        };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        // unknown denoms are rejected
+        let info = mock_info("alpha", &coins(1000, "sally"));
+        let msg = ExecuteMsg::Invest { referrer: None };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::ExtraDenom(_)));
+
+        do_invest(deps.as_mut(), "alpha", 1000);
+        let info = mock_info("beta", &coins(500, "brown"));
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Invest { referrer: None }).unwrap();
+
+        // each denom mints by its own ratio and keeps its own pot
+        let res = query_token_balance(deps.as_ref(), "beta".to_string()).unwrap();
+        assert_eq!(Uint128::new(1000), res.balance);
+        let res = query_current_investment(deps.as_ref()).unwrap();
+        assert_eq!(Uint128::new(1000), res.total_amount);
+        let res = query_pools(deps.as_ref(), 1).unwrap();
+        assert_eq!(Uint128::new(500), res.pools[0].total_amount);
+
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::reply_on_error(
+                BankMsg::Send {
+                    to_address: "alpha".to_string(),
+                    amount: coins(600, "cony"),
+                },
+                PAYOUT_REPLY_ID
+            )
+        );
+        assert_eq!(
+            res.messages[2],
+            SubMsg::reply_on_error(
+                BankMsg::Send {
+                    to_address: "beta".to_string(),
+                    amount: coins(300, "brown"),
+                },
+                PAYOUT_REPLY_ID + 2
+            )
+        );
+        let res = query_pools(deps.as_ref(), 1).unwrap();
+        assert_eq!(Uint128::new(300), res.pools[0].first_winner.as_ref().unwrap().amount);
+        // the owner cut of each pot accrues in its denom
+        let res = query_fees(deps.as_ref()).unwrap();
+        assert_eq!(vec![Coin::new(10, "brown"), Coin::new(20, "cony")], res.fees);
+
+        // a treasurer withdraws them
+        let msg = ExecuteMsg::WithdrawFees {};
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg.clone())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::MissingRole { role: Role::Treasurer }));
+        let res = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: vec![Coin::new(10, "brown"), Coin::new(20, "cony")],
+            }),
+            res.messages[0].msg
+        );
+        let err = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+    }
+
+    #[test]
+    fn invest_cw20() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+
+        do_instantiate(deps.as_mut(), auth_info.clone());
+        let msg = ExecuteMsg::AddDenom {
+            denom: "cw20:token".to_string(),
+            exchange_ratio: 10,
+        };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        let receive = |amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "alpha".to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Invest { referrer: None }).unwrap(),
+            })
+        };
+        // only whitelisted cw20 contracts
+        let info = mock_info("other", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, receive(1000)).unwrap_err();
+        assert!(matches!(err, ContractError::ExtraDenom(_)));
+        execute(deps.as_mut(), mock_env(), mock_info("token", &[]), receive(1000)).unwrap();
+
+        let denom = "cw20:token".to_string();
+        let res = query_pool_investors(deps.as_ref(), 1, denom, None, None).unwrap();
+        assert_eq!(Uint128::new(1000), res.investors[0].amount);
+        let res = query_token_balance(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(10000), res.balance);
+
+        // prizes are paid with cw20 transfers
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: "token".to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: "alpha".to_string(),
+                        amount: Uint128::new(600),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                },
+                PAYOUT_REPLY_ID
+            )
+        );
+    }
+
+    #[test]
+    fn failed_payout_is_claimable() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &coins(1000, "cony"));
+
+        do_instantiate(deps.as_mut(), auth_info.clone());
+        do_invest(deps.as_mut(), "alpha", 1000);
+        do_invest(deps.as_mut(), "beta", 1000);
+
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(PAYOUT_REPLY_ID, res.messages[0].id);
+        assert_eq!(ReplyOn::Error, res.messages[0].reply_on);
+
+        // the first winner cannot receive funds
+        let winner = query_invest_result(deps.as_ref(), 1).unwrap().first_winner;
+        let failed = Reply {
+            id: PAYOUT_REPLY_ID,
+            result: SubMsgResult::Err("blocked address".to_string()),
+        };
+        reply(deps.as_mut(), mock_env(), failed).unwrap();
+        let res = query_claimable(deps.as_ref(), winner.addr.clone()).unwrap();
+        assert_eq!(coins(winner.amount.u128(), "cony"), res.amount);
+
+        let info = mock_info(winner.addr.as_str(), &[]);
+        let msg = ExecuteMsg::ClaimPrize {};
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(1, res.messages.len());
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+
+        // an id past the payouts of the close is unknown
+        let unknown = Reply {
+            id: PAYOUT_REPLY_ID + 2,
+            result: SubMsgResult::Err("blocked address".to_string()),
+        };
+        let err = reply(deps.as_mut(), mock_env(), unknown).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownReplyId { id } if id == PAYOUT_REPLY_ID + 2));
+    }
+
     #[test]
     fn transfer_token() {
         let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
@@ -454,4 +2232,1289 @@ mod tests {
         assert_eq!(Uint128::new(5000), query_token_balance(deps.as_ref(), spender1.clone()).unwrap().balance);
         assert_eq!(Uint128::new(15000), query_token_balance(deps.as_ref(), spender2.clone()).unwrap().balance);
     }
+
+    #[test]
+    fn pause() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        do_instantiate(deps.as_mut(), auth_info.clone());
+        do_invest(deps.as_mut(), "alpha", 1000);
+
+        let msg = ExecuteMsg::Pause {
+            reason: "draw audit".to_string(),
+            operations: Some(vec![PausableOperation::Invest, PausableOperation::Close]),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), msg.clone())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::MissingRole {
+            role: Role::Guardian
+        }));
+        let grant = ExecuteMsg::GrantRole {
+            role: Role::Guardian,
+            addr: "guard".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), grant).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), msg).unwrap();
+        assert_eq!("Paused", attribute(&res, "action"));
+        assert_eq!("invest,close", attribute(&res, "operations"));
+
+        let info = mock_info("beta", &coins(1000, "cony"));
+        let msg = ExecuteMsg::Invest { referrer: None };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!("Paused: draw audit", err.to_string());
+        let msg = ExecuteMsg::CloseInvestment {};
+        let err = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Paused { .. }));
+
+        // transfers were not targeted, queries keep working
+        let msg = ExecuteMsg::TransferToken {
+            to: "beta".to_string(),
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg).unwrap();
+        let res = query_pause_info(deps.as_ref()).unwrap();
+        assert_eq!(Addr::unchecked("guard"), res.pause.unwrap().paused_by);
+
+        let res = execute(deps.as_mut(), mock_env(), auth_info.clone(), ExecuteMsg::Unpause {})
+            .unwrap();
+        assert_eq!("Unpaused", attribute(&res, "action"));
+        assert_eq!(None, query_pause_info(deps.as_ref()).unwrap().pause);
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+    }
+
+    #[test]
+    fn roles() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        do_instantiate(deps.as_mut(), auth_info.clone());
+        do_invest(deps.as_mut(), "alpha", 1000);
+
+        // the owner holds every role and may hand them out
+        let grant = |role: Role, addr: &str| ExecuteMsg::GrantRole {
+            role,
+            addr: addr.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), grant(Role::Admin, "admin")).unwrap();
+        let info = mock_info("operator", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, grant(Role::Operator, "operator"))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::MissingRole { role: Role::Admin }
+        ));
+        let info = mock_info("admin", &[]);
+        execute(deps.as_mut(), mock_env(), info, grant(Role::Operator, "operator")).unwrap();
+
+        let res = query_roles(deps.as_ref()).unwrap();
+        assert_eq!("creator", res.owner);
+        assert_eq!(vec!["admin".to_string()], res.roles[0].members);
+        assert_eq!(vec!["operator".to_string()], res.roles[1].members);
+
+        // closing a round takes the operator role
+        let msg = ExecuteMsg::CloseInvestment {};
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!("Unauthorized: missing the operator role", err.to_string());
+        execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::Operator,
+            addr: "operator".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+        do_invest(deps.as_mut(), "alpha", 1000);
+        let msg = ExecuteMsg::CloseInvestment {};
+        let err = execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::MissingRole {
+                role: Role::Operator
+            }
+        ));
+    }
+
+    #[test]
+    fn sudo_rounds() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            round_duration: Some(3600),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        do_invest(deps.as_mut(), "alpha", 1000);
+        let start = mock_env().block.time;
+        let deadline = query_current_investment(deps.as_ref()).unwrap().deadline;
+        assert_eq!(Some(start.plus_seconds(3600)), deadline);
+
+        // nothing happens before the deadline
+        let res = sudo(deps.as_mut(), mock_env(), SudoMsg::CloseDueRounds {}).unwrap();
+        assert_eq!("0", attribute(&res, "closed"));
+        assert_eq!("DueRoundsChecked", attribute(&res, "action"));
+        assert_eq!(1u32, query_current_round(deps.as_ref()).unwrap().round);
+
+        let mut env = mock_env();
+        env.block.time = start.plus_seconds(3600);
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::CloseDueRounds {}).unwrap();
+        assert_eq!("ClosedInvestment", attribute(&res, "action"));
+        assert_eq!(2u32, query_current_round(deps.as_ref()).unwrap().round);
+        assert_eq!("alpha", query_invest_result(deps.as_ref(), 1).unwrap().first_winner.addr);
+
+        // a due round without investors gets another period
+        env.block.time = start.plus_seconds(7200);
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::CloseDueRounds {}).unwrap();
+        assert_eq!("2", attribute(&res, "extended"));
+        let deadline = query_current_investment(deps.as_ref()).unwrap().deadline;
+        assert_eq!(Some(start.plus_seconds(10800)), deadline);
+
+        // only the current round can be cancelled, its investments become claimable and
+        // the LTT minted for them still held is burned
+        let err = sudo(deps.as_mut(), env.clone(), SudoMsg::ForceCancel { round: 1 }).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRound { round: 1 }));
+        do_invest(deps.as_mut(), "beta", 500);
+        let msg = ExecuteMsg::TransferToken {
+            to: "gamma".to_string(),
+            amount: Uint128::new(2000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("beta", &[]), msg).unwrap();
+        let res = sudo(deps.as_mut(), env, SudoMsg::ForceCancel { round: 2 }).unwrap();
+        assert_eq!("RoundCancelled", attribute(&res, "action"));
+        assert_eq!("1", attribute(&res, "refunds"));
+        assert_eq!("3000", attribute(&res, "burned"));
+        let balance = query_token_balance(deps.as_ref(), "beta".to_string()).unwrap();
+        assert_eq!(Uint128::zero(), balance.balance);
+        let balance = query_token_balance(deps.as_ref(), "gamma".to_string()).unwrap();
+        assert_eq!(Uint128::new(2000), balance.balance);
+        assert_eq!(3u32, query_current_round(deps.as_ref()).unwrap().round);
+        assert!(INVESTMENTS.load(&deps.storage, "2".to_string()).unwrap().cancelled);
+
+        let msg = ExecuteMsg::ClaimPrize {};
+        let res = execute(deps.as_mut(), mock_env(), mock_info("beta", &[]), msg).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beta".to_string(),
+                amount: coins(500, "cony"),
+            }),
+            res.messages[0].msg
+        );
+    }
+
+    #[test]
+    fn access_lists() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        do_instantiate(deps.as_mut(), auth_info.clone());
+        do_invest(deps.as_mut(), "alpha", 1000);
+
+        // blocked addresses can neither invest nor move LTT
+        let msg = ExecuteMsg::UpdateBlocklist {
+            add: vec!["mallory".to_string()],
+            remove: vec![],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg.clone())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::MissingRole { role: Role::Admin }));
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let info = mock_info("mallory", &coins(1000, "cony"));
+        let msg = ExecuteMsg::Invest { referrer: None };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!("Address mallory is blocked", err.to_string());
+        let msg = ExecuteMsg::TransferToken {
+            to: "mallory".to_string(),
+            amount: Uint128::new(100),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Blocked { .. }));
+        let res = query_blocklist(deps.as_ref(), None, None).unwrap();
+        assert_eq!(vec!["mallory".to_string()], res.members);
+
+        // in allowlist mode only written or proven members play
+        let leaf = |addr: &str| Sha256::digest(addr.as_bytes()).to_vec();
+        let (left, right) = if leaf("beta") <= leaf("gamma") {
+            (leaf("beta"), leaf("gamma"))
+        } else {
+            (leaf("gamma"), leaf("beta"))
+        };
+        let root = Sha256::new().chain_update(left).chain_update(right).finalize();
+        let msg = ExecuteMsg::SetAllowlist {
+            enabled: true,
+            merkle_root: Some(Binary::from(root.to_vec())),
+        };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateAllowlist {
+            add: vec!["alpha".to_string()],
+            remove: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        do_invest(deps.as_mut(), "alpha", 1000);
+        let info = mock_info("beta", &coins(1000, "cony"));
+        let msg = ExecuteMsg::Invest { referrer: None };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!("Address beta is not on the allowlist", err.to_string());
+
+        let msg = ExecuteMsg::JoinAllowlist {
+            proof: vec![Binary::from(leaf("beta"))],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("beta", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleProof {}));
+        let msg = ExecuteMsg::JoinAllowlist {
+            proof: vec![Binary::from(leaf("gamma"))],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("beta", &[]), msg).unwrap();
+        do_invest(deps.as_mut(), "beta", 1000);
+
+        let res = query_allowlist(deps.as_ref(), None, None).unwrap();
+        assert!(res.enabled);
+        assert_eq!(vec!["alpha".to_string()], res.members);
+
+        // a proof only holds under the root it was made for
+        let msg = ExecuteMsg::SetAllowlist {
+            enabled: true,
+            merkle_root: Some(Binary::from(leaf("gamma"))),
+        };
+        execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let info = mock_info("beta", &coins(1000, "cony"));
+        let msg = ExecuteMsg::Invest { referrer: None };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted { .. }));
+        do_invest(deps.as_mut(), "alpha", 1000);
+    }
+
+    #[test]
+    fn responsible_play() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        do_instantiate(deps.as_mut(), mock_info("creator", &[]));
+        let invest_at = |deps: DepsMut, env: Env, amount: u128| {
+            let info = mock_info("alpha", &coins(amount, "cony"));
+            execute(deps, env, info, ExecuteMsg::Invest { referrer: None })
+        };
+        let mut env = mock_env();
+        let start = env.block.time;
+
+        // deposits before any limit count against the limits set later
+        invest_at(deps.as_mut(), env.clone(), 500).unwrap();
+        let msg = ExecuteMsg::SetDepositLimit {
+            per_day: Some(Uint128::new(1000)),
+            per_week: Some(Uint128::new(1500)),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alpha", &[]), msg).unwrap();
+        invest_at(deps.as_mut(), env.clone(), 300).unwrap();
+        let err = invest_at(deps.as_mut(), env.clone(), 300).unwrap_err();
+        assert_eq!("Deposit limit per day exceeded: remaining Uint128(200)", err.to_string());
+
+        // the day window rolls over, the week one still counts the first deposit
+        env.block.time = start.plus_seconds(25 * 60 * 60);
+        invest_at(deps.as_mut(), env.clone(), 600).unwrap();
+        let err = invest_at(deps.as_mut(), env.clone(), 200).unwrap_err();
+        assert!(matches!(err, ContractError::DepositLimitExceeded { .. }));
+        let res = query_player_limits(deps.as_ref(), env.clone(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(600), res.deposited_day);
+        assert_eq!(Some(Uint128::new(100)), res.remaining_week);
+
+        // lifting the limits waits for the delay
+        let msg = ExecuteMsg::SetDepositLimit {
+            per_day: None,
+            per_week: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alpha", &[]), msg).unwrap();
+        invest_at(deps.as_mut(), env.clone(), 200).unwrap_err();
+        let res = query_player_limits(deps.as_ref(), env.clone(), "alpha".to_string()).unwrap();
+        let effective_at = res.pending.unwrap().effective_at;
+        assert_eq!(env.block.time.plus_seconds(LOOSENING_DELAY), effective_at);
+        env.block.time = effective_at;
+        invest_at(deps.as_mut(), env.clone(), 5000).unwrap();
+
+        // an exclusion holds at least for the delay, even when shortened
+        let until = env.block.time.plus_seconds(30 * 24 * 60 * 60);
+        let msg = ExecuteMsg::SetSelfExclusion { until };
+        execute(deps.as_mut(), env.clone(), mock_info("alpha", &[]), msg).unwrap();
+        let err = invest_at(deps.as_mut(), env.clone(), 100).unwrap_err();
+        assert!(matches!(err, ContractError::SelfExcluded { .. }));
+        let msg = ExecuteMsg::SetSelfExclusion {
+            until: env.block.time.plus_seconds(60),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alpha", &[]), msg).unwrap();
+        let res = query_player_limits(deps.as_ref(), env.clone(), "alpha".to_string()).unwrap();
+        let until = env.block.time.plus_seconds(LOOSENING_DELAY);
+        assert_eq!(Some(until), res.excluded_until);
+        env.block.time = until;
+        invest_at(deps.as_mut(), env, 100).unwrap();
+    }
+
+    #[test]
+    fn referrals() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            referral_share: Some(50),
+            ..default_instantiate_msg()
+        };
+        let auth_info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let invest = |deps: DepsMut, addr: &str, amount: u128, referrer: Option<&str>| {
+            let info = mock_info(addr, &coins(amount, "cony"));
+            let msg = ExecuteMsg::Invest {
+                referrer: referrer.map(String::from),
+            };
+            execute(deps, mock_env(), info, msg)
+        };
+
+        let err = invest(deps.as_mut(), "delta", 1000, Some("delta")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidParams {}));
+        invest(deps.as_mut(), "alpha", 1000, Some("ref")).unwrap();
+        invest(deps.as_mut(), "beta", 1000, None).unwrap();
+        invest(deps.as_mut(), "gamma", 2000, Some("ref")).unwrap();
+        // the referrer is bound for good on the first investment
+        invest(deps.as_mut(), "alpha", 1000, Some("other")).unwrap();
+        invest(deps.as_mut(), "beta", 1000, Some("other")).unwrap();
+        let res = query_referral_stats(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Some("ref".to_string()), res.referrer);
+        assert_eq!(0, query_referral_stats(deps.as_ref(), "other".to_string()).unwrap().referees);
+
+        // half of the 120 owner cut, 4000 of the 6000 invested were referred by "ref"
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let res = query_fees(deps.as_ref()).unwrap();
+        assert_eq!(coins(120 - 40, "cony"), res.fees);
+        let res = query_referral_stats(deps.as_ref(), "ref".to_string()).unwrap();
+        assert_eq!(2, res.referees);
+        assert_eq!(Uint128::new(40), res.earned);
+        assert_eq!(Uint128::new(40), res.claimable);
+
+        let msg = ExecuteMsg::ClaimReferralRewards {};
+        let res = execute(deps.as_mut(), mock_env(), mock_info("ref", &[]), msg.clone()).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ref".to_string(),
+                amount: coins(40, "cony"),
+            }),
+            res.messages[0].msg
+        );
+        let err = execute(deps.as_mut(), mock_env(), mock_info("ref", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+
+        // holding LTT is not entering, the referrer is bound on the first entry
+        let msg = ExecuteMsg::TransferToken {
+            to: "epsilon".to_string(),
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg).unwrap();
+        invest(deps.as_mut(), "epsilon", 1000, Some("ref")).unwrap();
+        assert_eq!(3, query_referral_stats(deps.as_ref(), "ref".to_string()).unwrap().referees);
+    }
+
+    #[test]
+    fn sponsor() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        do_instantiate(deps.as_mut(), auth_info.clone());
+
+        let info = mock_info("partner", &coins(1000, "cony"));
+        let msg = ExecuteMsg::Sponsor {
+            round: 2,
+            message: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRound { round: 2 }));
+        let msg = ExecuteMsg::Sponsor {
+            round: 1,
+            message: Some("brought to you by partner".to_string()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!("Sponsored", attribute(&res, "action"));
+        assert_eq!("brought to you by partner", attribute(&res, "message"));
+        do_invest(deps.as_mut(), "alpha", 1000);
+        do_invest(deps.as_mut(), "beta", 1000);
+
+        // no LTT and no draw entry for the sponsor
+        let total_supply = query_token_total_supply(deps.as_ref()).unwrap();
+        assert_eq!(Uint128::new(20000), total_supply.supply);
+        let investors = query_current_investors(deps.as_ref(), None, None).unwrap();
+        assert_eq!(2, investors.investors.len());
+        let res = query_sponsors(deps.as_ref(), 1).unwrap();
+        assert_eq!(Uint128::new(1000), res.total_amount);
+        assert_eq!("partner", res.sponsors[0].sponsor);
+
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let res = query_invest_result(deps.as_ref(), 1).unwrap();
+        assert_eq!(Uint128::new(3000 * 60 / 100), res.first_winner.amount);
+
+        // a round where only another denom is drawn keeps the sponsorship for the next one
+        let msg = ExecuteMsg::AddDenom {
+            denom: "brown".to_string(),
+            exchange_ratio: 2,
+        };
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let info = mock_info("partner", &coins(500, "cony"));
+        let msg = ExecuteMsg::Sponsor {
+            round: 2,
+            message: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("beta", &coins(100, "brown"));
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Invest { referrer: None }).unwrap();
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        let res = query_current_investment(deps.as_ref()).unwrap();
+        assert_eq!(Uint128::new(500), res.sponsored_amount);
+
+        do_invest(deps.as_mut(), "alpha", 1000);
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let res = query_invest_result(deps.as_ref(), 3).unwrap();
+        assert_eq!(Uint128::new(1500 * 60 / 100), res.first_winner.amount);
+    }
+
+    #[test]
+    fn subscriptions() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        do_instantiate(deps.as_mut(), auth_info.clone());
+
+        let msg = ExecuteMsg::Subscribe {
+            rounds: 3,
+            amount_per_round: Uint128::new(100),
+            referrer: None,
+        };
+        let info = mock_info("alpha", &coins(200, "cony"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPayment { .. }));
+        // the escrow is deposited at once, within the deposit limits
+        let limit = ExecuteMsg::SetDepositLimit {
+            per_day: Some(Uint128::new(250)),
+            per_week: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), limit).unwrap();
+        let info = mock_info("alpha", &coins(300, "cony"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::DepositLimitExceeded { .. }));
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(LOOSENING_DELAY);
+        let limit = ExecuteMsg::SetDepositLimit {
+            per_day: None,
+            per_week: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), limit).unwrap();
+        let res = execute(deps.as_mut(), env, info.clone(), msg.clone()).unwrap();
+        assert_eq!("2", attribute(&res, "start_round"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadySubscribed {}));
+
+        // entered from the next round on
+        do_invest(deps.as_mut(), "beta", 1000);
+        let investors = query_current_investors(deps.as_ref(), None, None).unwrap();
+        assert_eq!(1, investors.investors.len());
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        assert_eq!("1", attribute(&res, "subscribers_entered"));
+
+        let investors = query_current_investors(deps.as_ref(), None, None).unwrap();
+        assert_eq!(2, investors.round);
+        assert_eq!(
+            vec![Investor {
+                addr: "alpha".to_string(),
+                amount: Uint128::new(100),
+            }],
+            investors.investors
+        );
+        let balance = query_token_balance(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(1000), balance.balance);
+        let res = query_subscriptions(deps.as_ref(), None, None).unwrap();
+        assert_eq!(2, res.subscriptions[0].remaining_rounds);
+
+        // the opening batch entered everyone already
+        let msg = ExecuteMsg::ProcessSubscriptions { limit: None };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("any", &[]), msg).unwrap();
+        assert_eq!("0", attribute(&res, "entered"));
+
+        let msg = ExecuteMsg::Unsubscribe {};
+        let info = mock_info("alpha", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alpha".to_string(),
+                amount: coins(200, "cony"),
+            }),
+            res.messages[0].msg
+        );
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotSubscribed {}));
+        let res = query_subscriptions(deps.as_ref(), None, None).unwrap();
+        assert!(res.subscriptions.is_empty());
+    }
+
+    #[test]
+    fn auto_reinvest() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        do_instantiate(deps.as_mut(), auth_info.clone());
+
+        let info = mock_info("alpha", &[]);
+        let msg = ExecuteMsg::SetAutoReinvest {
+            enabled: true,
+            percent: 101,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidParams {}));
+        let msg = ExecuteMsg::SetAutoReinvest {
+            enabled: true,
+            percent: 50,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query_auto_reinvest(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert!(res.enabled);
+        assert_eq!(50, res.percent);
+
+        // "alpha" wins the first prize of 1200 and reinvests half, "beta" the second of 400
+        do_invest(deps.as_mut(), "alpha", 1000);
+        do_invest(deps.as_mut(), "beta", 1000);
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        assert_eq!("600", attribute(&res, "reinvested"));
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alpha".to_string(),
+                amount: coins(600, "cony"),
+            }),
+            res.messages[0].msg
+        );
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beta".to_string(),
+                amount: coins(400, "cony"),
+            }),
+            res.messages[1].msg
+        );
+
+        let investors = query_current_investors(deps.as_ref(), None, None).unwrap();
+        assert_eq!(2, investors.round);
+        assert_eq!(
+            vec![Investor {
+                addr: "alpha".to_string(),
+                amount: Uint128::new(600),
+            }],
+            investors.investors
+        );
+        let balance = query_token_balance(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(10000 + 6000), balance.balance);
+    }
+
+    #[test]
+    fn prize_vesting() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            vesting: Some(VestingConfig {
+                threshold: Uint128::new(1000),
+                duration: 1000,
+            }),
+            ..default_instantiate_msg()
+        };
+        let auth_info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        // the first prize of 1200 to "alpha" is vested, the second one is sent
+        do_invest(deps.as_mut(), "beta", 1000);
+        do_invest(deps.as_mut(), "alpha", 1000);
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg.clone()).unwrap();
+        assert_eq!("1200", attribute(&res, "vested"));
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beta".to_string(),
+                amount: coins(400, "cony"),
+            }),
+            res.messages[0].msg
+        );
+
+        let info = mock_info("alpha", &[]);
+        let claim = ExecuteMsg::ClaimVested {};
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), claim.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(250);
+        let res = query_vesting(deps.as_ref(), env.clone(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(300), res.schedules[0].claimable);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), claim.clone()).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alpha".to_string(),
+                amount: coins(300, "cony"),
+            }),
+            res.messages[0].msg
+        );
+        env.block.time = env.block.time.plus_seconds(1000);
+        let res = execute(deps.as_mut(), env.clone(), info, claim).unwrap();
+        assert_eq!("900", attribute(&res, "amount"));
+        let res = query_vesting(deps.as_ref(), env, "alpha".to_string()).unwrap();
+        assert!(res.schedules.is_empty());
+
+        // a first prize below the threshold is sent at once
+        do_invest(deps.as_mut(), "beta", 100);
+        do_invest(deps.as_mut(), "alpha", 100);
+        let res = execute(deps.as_mut(), mock_env(), auth_info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alpha".to_string(),
+                amount: coins(120, "cony"),
+            }),
+            res.messages[0].msg
+        );
+
+        // the threshold applies to what is left after a reinvest, 600 of the 1200
+        for who in ["alpha", "beta"] {
+            let reinvest = ExecuteMsg::SetAutoReinvest {
+                enabled: true,
+                percent: 50,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(who, &[]), reinvest).unwrap();
+            do_invest(deps.as_mut(), who, 1000);
+        }
+        let res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        assert_eq!("800", attribute(&res, "reinvested"));
+        assert!(res.attributes.iter().all(|a| a.key != "vested"));
+        assert_eq!(2, res.messages.len());
+    }
+
+    #[test]
+    fn claim_expiry() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            claim_expiry: Some(ClaimExpiry {
+                window: 100,
+                sweep_to: SweepTarget::Pot,
+            }),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // the refunds of a cancelled round are claims with a window
+        do_invest(deps.as_mut(), "alpha", 1000);
+        do_invest(deps.as_mut(), "beta", 500);
+        sudo(deps.as_mut(), mock_env(), SudoMsg::ForceCancel { round: 1 }).unwrap();
+        let msg = ExecuteMsg::SweepExpired { round: 1 };
+        let info = mock_info("any", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::ClaimsNotExpired { .. }));
+        let claim = ExecuteMsg::ClaimPrize {};
+        execute(deps.as_mut(), mock_env(), mock_info("beta", &[]), claim).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(5, res.attributes.len());
+        assert_eq!("ClaimExpired", attribute(&res, "action"));
+        assert_eq!("alpha", attribute(&res, "who"));
+        assert_eq!("pot", attribute(&res, "to"));
+        let res = query_claimable(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert!(res.amount.is_empty());
+        let res = query_current_investment(deps.as_ref()).unwrap();
+        assert_eq!(Uint128::new(1000), res.swept_amount);
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+
+        // a cancel refunds nobody for it, the swept amount goes to the next prize
+        sudo(deps.as_mut(), env, SudoMsg::ForceCancel { round: 2 }).unwrap();
+        let res = query_current_investment(deps.as_ref()).unwrap();
+        assert_eq!(3, res.round);
+        assert_eq!(Uint128::new(1000), res.swept_amount);
+        // as does a close which only draws the pools of other denoms
+        let msg = ExecuteMsg::AddDenom {
+            denom: "brown".to_string(),
+            exchange_ratio: 2,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let info = mock_info("gamma", &coins(100, "brown"));
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Invest { referrer: None }).unwrap();
+        let close = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), close.clone()).unwrap();
+        let res = query_current_investment(deps.as_ref()).unwrap();
+        assert_eq!(4, res.round);
+        assert_eq!(Uint128::new(1000), res.swept_amount);
+
+        do_invest(deps.as_mut(), "beta", 1000);
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), close).unwrap();
+        let res = query_invest_result(deps.as_ref(), 4).unwrap();
+        assert_eq!(Uint128::new(2000 * 60 / 100), res.first_winner.amount);
+    }
+
+    #[test]
+    fn dividends() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            dividend_share: Some(50),
+            ..default_instantiate_msg()
+        };
+        let auth_info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+
+        // half of the 40 owner cut goes to the 20000 LTT, 10 to each holder
+        do_invest(deps.as_mut(), "alpha", 1000);
+        do_invest(deps.as_mut(), "beta", 1000);
+        let close = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), close.clone()).unwrap();
+        let res = query_fees(deps.as_ref()).unwrap();
+        assert_eq!(coins(20, "cony"), res.fees);
+        let res = query_dividends(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(10), res.claimable);
+
+        // a transfer settles both sides, "gamma" only earns from then on
+        let msg = ExecuteMsg::TransferToken {
+            to: "gamma".to_string(),
+            amount: Uint128::new(5000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg).unwrap();
+        do_invest(deps.as_mut(), "alpha", 1000);
+        do_invest(deps.as_mut(), "beta", 1000);
+        execute(deps.as_mut(), mock_env(), auth_info, close).unwrap();
+        // 20 over 40000 LTT, rounded down per holder
+        let res = query_dividends(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(10 + 7), res.claimable);
+        let res = query_dividends(deps.as_ref(), "gamma".to_string()).unwrap();
+        assert_eq!(Uint128::new(2), res.claimable);
+
+        let msg = ExecuteMsg::ClaimDividends {};
+        let info = mock_info("alpha", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alpha".to_string(),
+                amount: coins(17, "cony"),
+            }),
+            res.messages[0].msg
+        );
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+    }
+
+    #[test]
+    fn redeem_for_ticket() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            redeem_rate: Some(Uint128::new(100)),
+            ..default_instantiate_msg()
+        };
+        let auth_info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), auth_info.clone(), msg).unwrap();
+        do_invest(deps.as_mut(), "alpha", 1000);
+        do_invest(deps.as_mut(), "beta", 1000);
+        let msg = ExecuteMsg::TransferToken {
+            to: "gamma".to_string(),
+            amount: Uint128::new(5000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg).unwrap();
+
+        let info = mock_info("gamma", &[]);
+        let msg = ExecuteMsg::RedeemForTicket {
+            amount: Uint128::new(50),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidZeroAmount {}));
+        // only whole units are burned
+        let msg = ExecuteMsg::RedeemForTicket {
+            amount: Uint128::new(250),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!("200", attribute(&res, "burned"));
+        assert_eq!("2", attribute(&res, "weight"));
+        let balance = query_token_balance(deps.as_ref(), "gamma".to_string()).unwrap();
+        assert_eq!(Uint128::new(4800), balance.balance);
+        let total_supply = query_token_total_supply(deps.as_ref()).unwrap();
+        assert_eq!(Uint128::new(19800), total_supply.supply);
+        let res = query_bonus_weights(deps.as_ref(), 1).unwrap();
+        assert_eq!(Uint128::new(2), res.weights[0].amount);
+
+        // the prize is only what was paid
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), auth_info.clone(), msg.clone()).unwrap();
+        let res = query_invest_result(deps.as_ref(), 1).unwrap();
+        assert_eq!(Uint128::new(2000 * 60 / 100), res.first_winner.amount);
+        assert_eq!(Uint128::new(2000 * 20 / 100), res.second_winner.amount);
+
+        // a round entered by bonus weight only is still drawn
+        let info = mock_info("gamma", &[]);
+        let redeem = ExecuteMsg::RedeemForTicket {
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), info, redeem).unwrap();
+        execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        let res = query_invest_result(deps.as_ref(), 2).unwrap();
+        assert_eq!("gamma", res.first_winner.addr);
+        assert_eq!("gamma", res.second_winner.addr);
+        assert_eq!(Uint128::zero(), res.first_winner.amount);
+    }
+
+    #[test]
+    fn redeem_for_free_tickets() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let msg = InstantiateMsg {
+            tickets: Some(TicketConfig {
+                price: Uint128::new(100),
+                overpayment: OverpaymentPolicy::Reject,
+            }),
+            redeem_rate: Some(Uint128::new(100)),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let info = mock_info("alpha", &coins(1100, "cony"));
+        let msg = ExecuteMsg::BuyTickets {
+            count: 11,
+            referrer: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // too many tickets at once burn nothing
+        let info = mock_info("alpha", &[]);
+        let msg = ExecuteMsg::RedeemForTicket {
+            amount: Uint128::new(10100),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidParams {}));
+        let balance = query_token_balance(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(11000), balance.balance);
+
+        // the units are tickets, no bonus weight is kept
+        let msg = ExecuteMsg::RedeemForTicket {
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query_tickets(deps.as_ref(), 1, "alpha".to_string(), Some(11), None).unwrap();
+        assert_eq!(vec![12, 13], res.tickets);
+        let res = query_bonus_weights(deps.as_ref(), 1).unwrap();
+        assert!(res.weights.is_empty());
+    }
+
+    #[test]
+    fn weighted_draw() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        do_instantiate(deps.as_mut(), mock_info("creator", &[]));
+        let contract = CONTRACT_INFO.load(deps.as_ref().storage).unwrap();
+        let investors = vec![
+            Investor {
+                addr: "alpha".to_string(),
+                amount: Uint128::new(3000),
+            },
+            Investor {
+                addr: "beta".to_string(),
+                amount: Uint128::new(1000),
+            },
+            Investor {
+                addr: "gamma".to_string(),
+                amount: Uint128::zero(),
+            },
+        ];
+
+        // odds follow the amounts, nothing entered never wins first
+        let mut alpha_first = 0;
+        for n in 0u32..1000 {
+            let seed = n.to_be_bytes();
+            let winners = draw_winners(&contract, &investors, Uint128::new(100), &seed);
+            assert_ne!("gamma", winners[0].addr);
+            assert_ne!(winners[0].addr, winners[1].addr);
+            if winners[0].addr == "alpha" {
+                alpha_first += 1;
+            }
+        }
+        assert!((700..800).contains(&alpha_first), "{}", alpha_first);
+    }
+
+    #[test]
+    fn balance_snapshots() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        do_instantiate(deps.as_mut(), mock_info("creator", &[]));
+        let height = mock_env().block.height;
+        do_invest(deps.as_mut(), "alpha", 1000);
+
+        let mut env = mock_env();
+        env.block.height = height + 1;
+        let msg = ExecuteMsg::TransferToken {
+            to: "beta".to_string(),
+            amount: Uint128::new(4000),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alpha", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Invest { referrer: None };
+        execute(deps.as_mut(), env, mock_info("beta", &coins(500, "cony")), msg).unwrap();
+
+        // a height reads the values as of the start of its block
+        let balance_at = |deps: Deps, addr: &str, height: u64| {
+            query_balance_at_height(deps, addr.to_string(), height)
+                .unwrap()
+                .balance
+                .u128()
+        };
+        assert_eq!(0, balance_at(deps.as_ref(), "alpha", height));
+        assert_eq!(10000, balance_at(deps.as_ref(), "alpha", height + 1));
+        assert_eq!(6000, balance_at(deps.as_ref(), "alpha", height + 2));
+        assert_eq!(0, balance_at(deps.as_ref(), "beta", height + 1));
+        assert_eq!(9000, balance_at(deps.as_ref(), "beta", height + 2));
+        let supply_at = |deps: Deps, height: u64| {
+            query_total_supply_at_height(deps, height).unwrap().supply.u128()
+        };
+        assert_eq!(0, supply_at(deps.as_ref(), height));
+        assert_eq!(10000, supply_at(deps.as_ref(), height + 1));
+        assert_eq!(15000, supply_at(deps.as_ref(), height + 2));
+    }
+
+    fn mock_delegation(validator: &str, amount: u128, rewards: u128) -> FullDelegation {
+        FullDelegation {
+            delegator: Addr::unchecked(mock_env().contract.address),
+            validator: validator.to_string(),
+            amount: Coin::new(amount, "cony"),
+            can_redelegate: Coin::new(amount, "cony"),
+            accumulated_rewards: coins(rewards, "cony"),
+        }
+    }
+
+    fn mock_validator(address: &str) -> Validator {
+        Validator {
+            address: address.to_string(),
+            commission: Decimal::percent(1),
+            max_commission: Decimal::percent(10),
+            max_change_rate: Decimal::percent(1),
+        }
+    }
+
+    fn weighted(address: &str, weight: u64) -> WeightedValidator {
+        WeightedValidator {
+            address: address.to_string(),
+            weight,
+        }
+    }
+
+    fn do_instantiate_no_loss(deps: DepsMut, validators: Vec<WeightedValidator>) {
+        let msg = InstantiateMsg {
+            no_loss: Some(StakingConfig {
+                validators,
+                unbonding_period: 100,
+            }),
+            ..default_instantiate_msg()
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn no_loss_invest_and_close() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let auth_info = mock_info("creator", &[]);
+        do_instantiate_no_loss(
+            deps.as_mut(),
+            vec![weighted("valoper1", 1), weighted("valoper2", 1)],
+        );
+
+        // invested principal is delegated
+        let info = mock_info("alpha", &coins(1001, "cony"));
+        let msg = ExecuteMsg::Invest { referrer: None };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(StakingMsg::Delegate {
+                    validator: "valoper1".to_string(),
+                    amount: Coin::new(501, "cony"),
+                }),
+                SubMsg::new(StakingMsg::Delegate {
+                    validator: "valoper2".to_string(),
+                    amount: Coin::new(500, "cony"),
+                }),
+            ],
+            res.messages
+        );
+        assert_eq!(Uint128::new(1001), query_staking_info(deps.as_ref()).unwrap().total_staked);
+
+        deps.querier.update_staking(
+            "cony",
+            &[mock_validator("valoper1"), mock_validator("valoper2")],
+            &[
+                mock_delegation("valoper1", 501, 100),
+                mock_delegation("valoper2", 500, 100),
+            ],
+        );
+
+        // only the rewards are drawn
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+        assert_eq!(4, res.messages.len());
+        let res = query_invest_result(deps.as_ref(), 1).unwrap();
+        assert_eq!(Uint128::new(200 * 60 / 100), res.first_winner.amount);
+        assert_eq!(Uint128::new(200 * 20 / 100), res.second_winner.amount);
+
+        // principal comes back after the unbonding period of its batch
+        let info = mock_info("alpha", &[]);
+        let msg = ExecuteMsg::Unbond { amount: Uint128::new(1001) };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert!(res.messages.is_empty());
+        let msg = ExecuteMsg::Rebalance {};
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            Uint128::zero(),
+            query_principal(deps.as_ref(), "alpha".to_string()).unwrap().principal
+        );
+
+        let msg = ExecuteMsg::WithdrawPrincipal {};
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(101);
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alpha".to_string(),
+                amount: coins(1001, "cony"),
+            })],
+            res.messages
+        );
+    }
+
+    #[test]
+    fn no_loss_unbond() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        do_instantiate_no_loss(deps.as_mut(), vec![weighted("valoper1", 1)]);
+        do_invest(deps.as_mut(), "alpha", 1000);
+        do_invest(deps.as_mut(), "beta", 1000);
+        deps.querier.update_staking(
+            "cony",
+            &[mock_validator("valoper1")],
+            &[mock_delegation("valoper1", 2000, 50)],
+        );
+
+        // the unbonds wait for the batch, undelegated at once
+        let info = mock_info("beta", &[]);
+        let msg = ExecuteMsg::Unbond { amount: Uint128::new(300) };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!("0", attribute(&res, "batch"));
+        let msg = ExecuteMsg::Unbond { amount: Uint128::new(200) };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let res = query_principal(deps.as_ref(), "beta".to_string()).unwrap();
+        assert_eq!(2, res.unbondings.len());
+        assert_eq!(None, res.unbondings[0].release_at);
+
+        // the undelegation matures when the chain completes it
+        let rebalance = ExecuteMsg::Rebalance {};
+        let anyone = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), anyone.clone(), rebalance.clone()).unwrap();
+        assert_eq!("BatchUndelegated", attribute(&res, "action"));
+        assert_eq!(
+            Uint128::zero(),
+            query_staking_info(deps.as_ref()).unwrap().unbond_batch.amount
+        );
+        assert_eq!(
+            vec![SubMsg::reply_on_success(
+                StakingMsg::Undelegate {
+                    validator: "valoper1".to_string(),
+                    amount: Coin::new(500, "cony"),
+                },
+                UNBOND_REPLY_ID,
+            )],
+            res.messages
+        );
+        let completion = Timestamp::from_seconds(1571799600);
+        let event = cosmwasm_std::Event::new("unbond")
+            .add_attribute("validator", "valoper1")
+            .add_attribute("amount", "500cony")
+            .add_attribute("completion_time", "2019-10-23T03:00:00Z");
+        let reply_msg = Reply {
+            id: UNBOND_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![event],
+                data: None,
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        assert_eq!("1571799600", attribute(&res, "release_at"));
+        let queue = query_unbonding_queue(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(completion, queue.unbondings[0].matures_at);
+        let res = query_principal(deps.as_ref(), "beta".to_string()).unwrap();
+        assert!(res.unbondings.iter().all(|u| u.release_at == Some(completion)));
+
+        let msg = ExecuteMsg::WithdrawPrincipal {};
+        let mut env = mock_env();
+        env.block.time = completion;
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+        env.block.time = completion.plus_seconds(1);
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        // the rewards paid out by the undelegation are drawn with the accrued ones
+        deps.querier.update_staking(
+            "cony",
+            &[mock_validator("valoper1")],
+            &[mock_delegation("valoper1", 1500, 30)],
+        );
+        let msg = ExecuteMsg::CloseInvestment {};
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let res = query_invest_result(deps.as_ref(), 1).unwrap();
+        assert_eq!(Uint128::new(80 * 60 / 100), res.first_winner.amount);
+        assert_eq!(Uint128::new(80 * 20 / 100), res.second_winner.amount);
+
+        // the next batch is undelegated an epoch after the last one
+        let msg = ExecuteMsg::Unbond { amount: Uint128::new(100) };
+        execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg).unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100 / UNBOND_EPOCHS - 1);
+        let res = execute(deps.as_mut(), env.clone(), anyone.clone(), rebalance.clone()).unwrap();
+        assert!(res.messages.is_empty());
+        env.block.time = env.block.time.plus_seconds(1);
+        let res = execute(deps.as_mut(), env, anyone, rebalance).unwrap();
+        assert_eq!("1", attribute(&res, "batch"));
+        assert_eq!(1, res.messages.len());
+    }
+
+    #[test]
+    fn no_loss_force_cancel() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        do_instantiate_no_loss(deps.as_mut(), vec![weighted("valoper1", 1)]);
+        do_invest(deps.as_mut(), "alpha", 1000);
+
+        // the principal stays deposited, nothing is refunded nor burned
+        let res = sudo(deps.as_mut(), mock_env(), SudoMsg::ForceCancel { round: 1 }).unwrap();
+        assert_eq!("0", attribute(&res, "refunds"));
+        assert_eq!("0", attribute(&res, "burned"));
+        let res = query_claimable(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert!(res.amount.is_empty());
+        assert_eq!(
+            Uint128::new(1000),
+            query_principal(deps.as_ref(), "alpha".to_string()).unwrap().principal
+        );
+        let balance = query_token_balance(deps.as_ref(), "alpha".to_string()).unwrap();
+        assert_eq!(Uint128::new(10000), balance.balance);
+        assert_eq!(2u32, query_current_round(deps.as_ref()).unwrap().round);
+    }
+
+    #[test]
+    fn no_loss_validators() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        do_instantiate_no_loss(
+            deps.as_mut(),
+            vec![weighted("valoper1", 1), weighted("valoper2", 1)],
+        );
+        do_invest(deps.as_mut(), "alpha", 1001);
+        deps.querier.update_staking(
+            "cony",
+            &[
+                mock_validator("valoper1"),
+                mock_validator("valoper2"),
+                mock_validator("valoper3"),
+            ],
+            &[
+                mock_delegation("valoper1", 501, 0),
+                mock_delegation("valoper2", 500, 0),
+            ],
+        );
+
+        // only admins manage validators
+        let msg = ExecuteMsg::UpdateValidators {
+            validators: vec![weighted("valoper1", 1), weighted("valoper3", 3)],
+        };
+        let info = mock_info("alpha", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::MissingRole {
+            role: Role::Admin
+        }));
+        let overflow = ExecuteMsg::UpdateValidators {
+            validators: vec![weighted("valoper1", u64::MAX), weighted("valoper3", 1)],
+        };
+        let info = mock_info("creator", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, overflow).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidParams {}));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(StakingMsg::Redelegate {
+                    src_validator: "valoper1".to_string(),
+                    dst_validator: "valoper3".to_string(),
+                    amount: Coin::new(250, "cony"),
+                }),
+                SubMsg::new(StakingMsg::Redelegate {
+                    src_validator: "valoper2".to_string(),
+                    dst_validator: "valoper3".to_string(),
+                    amount: Coin::new(500, "cony"),
+                }),
+            ],
+            res.messages
+        );
+
+        // a jailed validator drops out of the active set and its stake is spread
+        deps.querier.update_staking(
+            "cony",
+            &[mock_validator("valoper1")],
+            &[
+                mock_delegation("valoper1", 251, 0),
+                mock_delegation("valoper3", 750, 0),
+            ],
+        );
+        // closing a round leaves the stake in place, anyone may rebalance it
+        let msg = ExecuteMsg::CloseInvestment {};
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert!(res
+            .messages
+            .iter()
+            .all(|m| !matches!(m.msg, CosmosMsg::Staking(_))));
+        let msg = ExecuteMsg::Rebalance {};
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(StakingMsg::Redelegate {
+                src_validator: "valoper3".to_string(),
+                dst_validator: "valoper1".to_string(),
+                amount: Coin::new(750, "cony"),
+            })],
+            res.messages
+        );
+        let res = query_delegations(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(1, res.delegations.len());
+        assert_eq!("valoper1", res.delegations[0].validator);
+
+        // undelegations are tracked until they mature
+        let msg = ExecuteMsg::Unbond { amount: Uint128::new(100) };
+        execute(deps.as_mut(), mock_env(), mock_info("alpha", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Rebalance {};
+        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        let res = query_unbonding_queue(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(1, res.unbondings.len());
+        assert_eq!(Uint128::new(100), res.unbondings[0].amount);
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = query_unbonding_queue(deps.as_ref(), env).unwrap();
+        assert!(res.unbondings.is_empty());
+    }
+
+    #[test]
+    fn inactive_validators() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let mut config = StakingConfig {
+            validators: vec![weighted("valoper1", 1), weighted("valoper2", 1)],
+            unbonding_period: 100,
+        };
+
+        // valoper2 is jailed, it left the active set
+        deps.querier.update_staking(
+            "cony",
+            &[mock_validator("valoper1")],
+            &[
+                mock_delegation("valoper1", 500, 0),
+                mock_delegation("valoper2", 500, 0),
+            ],
+        );
+        let (msgs, removed) =
+            drop_inactive_validators(deps.as_ref(), &mock_env(), &mut config, "cony").unwrap();
+        assert_eq!(vec!["valoper2".to_string()], removed);
+        assert_eq!(vec![weighted("valoper1", 1)], config.validators);
+        assert_eq!(
+            vec![SubMsg::new(StakingMsg::Redelegate {
+                src_validator: "valoper2".to_string(),
+                dst_validator: "valoper1".to_string(),
+                amount: Coin::new(500, "cony"),
+            })],
+            msgs
+        );
+
+        // the stake stays put without any active validator left
+        deps.querier
+            .update_staking("cony", &[], &[mock_delegation("valoper1", 1000, 0)]);
+        let err =
+            drop_inactive_validators(deps.as_ref(), &mock_env(), &mut config, "cony").unwrap_err();
+        assert!(matches!(err, ContractError::NoActiveValidators {}));
+    }
 }
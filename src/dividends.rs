@@ -0,0 +1,73 @@
+use cosmwasm_std::{
+    Addr, BankMsg, Coin, Decimal, DepsMut, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::event::{DividendsClaimedEvent, Event};
+use crate::state::{
+    HolderDividends, BALANCES, CONTRACT_INFO, DIVIDEND_HOLDERS, DIVIDEND_INDEX, DIVIDEND_SHARE,
+    TOKEN_INFO,
+};
+
+/// Dividends of `addr` as of the current index, with those settled before.
+pub fn pending_dividends(storage: &dyn Storage, addr: &Addr) -> StdResult<HolderDividends> {
+    let index = DIVIDEND_INDEX.may_load(storage)?.unwrap_or_default();
+    let mut holder = DIVIDEND_HOLDERS
+        .may_load(storage, addr)?
+        .unwrap_or_default();
+    let balance = BALANCES.may_load(storage, addr)?.unwrap_or_default();
+    let earned = balance * (index - holder.index);
+    holder.pending = holder.pending.checked_add(earned)?;
+    holder.index = index;
+    Ok(holder)
+}
+
+/// Checkpoint the dividends of `addr`. Called before each change of its LTT balance, so the
+/// balance a holder earned with is the one it had since the last checkpoint.
+pub fn settle_dividends(storage: &mut dyn Storage, addr: &Addr) -> StdResult<()> {
+    let holder = pending_dividends(storage, addr)?;
+    DIVIDEND_HOLDERS.save(storage, addr, &holder)
+}
+
+/// Share the dividend part of the owner cut `fee` among the LTT holders by raising the
+/// reward index per token. Returns the amount taken from the owner cut.
+pub fn distribute_dividends(
+    storage: &mut dyn Storage,
+    fee: Uint128,
+) -> Result<Uint128, ContractError> {
+    let share = match DIVIDEND_SHARE.may_load(storage)? {
+        Some(share) if !fee.is_zero() => share,
+        _ => return Ok(Uint128::zero()),
+    };
+    let supply = TOKEN_INFO.load(storage)?.total_supply;
+    if supply.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    // the index is rounded down, the dust is left in the owner cut
+    let per_token = Decimal::from_ratio(fee.multiply_ratio(share as u128, 100u128), supply);
+    let index = DIVIDEND_INDEX.may_load(storage)?.unwrap_or_default();
+    DIVIDEND_INDEX.save(storage, &(index + per_token))?;
+    Ok(supply * per_token)
+}
+
+pub fn handle_claim_dividends(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut holder = pending_dividends(deps.storage, &info.sender)?;
+    if holder.pending.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    let amount = holder.pending;
+    holder.pending = Uint128::zero();
+    DIVIDEND_HOLDERS.save(deps.storage, &info.sender, &holder)?;
+
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let mut rsp = Response::new().add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin::new(amount.u128(), &contract.use_denom)],
+    });
+    DividendsClaimedEvent {
+        who: info.sender.as_str(),
+        amount,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
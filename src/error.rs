@@ -1,14 +1,22 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError, Timestamp, Uint128};
 use thiserror::Error;
 
+use crate::state::Role;
+
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Unauthorized: missing the {role} role")]
+    MissingRole { role: Role },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
     // Add any other custom errors you like here.
@@ -37,4 +45,112 @@ pub enum ContractError {
 
     #[error("Invalid zero amount")]
     InvalidZeroAmount {},
+
+    #[error("No investors in round: {round:?}")]
+    NoInvestors { round: u32 },
+
+    #[error("No-loss mode is not enabled")]
+    NoLossDisabled {},
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
+
+    #[error("No active validators")]
+    NoActiveValidators {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Unknown reply id: {id:?}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Only tickets can be bought in ticket mode")]
+    TicketsOnly {},
+
+    #[error("Ticket mode is not enabled")]
+    TicketsDisabled {},
+
+    #[error("Lotto mode is not enabled")]
+    LottoDisabled {},
+
+    #[error("No lotto seed left on the hash chain, wait for the owner to commit a new one")]
+    LottoChainExhausted {},
+
+    #[error("The lotto seed of round {round} is not revealed yet")]
+    LottoSeedNotRevealed { round: u32 },
+
+    #[error("Ticket sales of round {round} are closed")]
+    LottoSalesClosed { round: u32 },
+
+    #[error("Invalid lotto numbers")]
+    InvalidNumbers {},
+
+    #[error("Scratch cards are not enabled")]
+    ScratchDisabled {},
+
+    #[error("Preimage does not match the hash chain")]
+    InvalidPreimage {},
+
+    #[error("No scratch play left on the hash chain, wait for the owner to commit a new one")]
+    ScratchChainExhausted {},
+
+    #[error("Scratch plays are waiting for the reveal of their link")]
+    ScratchPlaysPending {},
+
+    #[error("No scratch play is overdue for its reveal")]
+    ScratchRevealNotDue {},
+
+    #[error("Insufficient bankroll: needed {needed:?}, available {available:?}")]
+    InsufficientBankroll { needed: Uint128, available: Uint128 },
+
+    #[error("Paused: {reason}")]
+    Paused { reason: String },
+
+    #[error("Unknown lottery template: {name}")]
+    UnknownTemplate { name: String },
+
+    #[error("Factory mode is not enabled")]
+    FactoryDisabled {},
+
+    #[error("A template must set the owner of its lotteries")]
+    TemplateWithoutOwner {},
+
+    #[error("Investment below the minimum: min {min:?}, received {received:?}")]
+    BelowMinInvest { min: Uint128, received: Uint128 },
+
+    #[error("Over the per-address cap: available {available:?}, received {received:?}")]
+    AddressCapExceeded { available: Uint128, received: Uint128 },
+
+    #[error("Over the pot cap: available {available:?}, received {received:?}")]
+    PotCapExceeded { available: Uint128, received: Uint128 },
+
+    #[error("Address {addr} is blocked")]
+    Blocked { addr: String },
+
+    #[error("Address {addr} is not on the allowlist")]
+    NotAllowlisted { addr: String },
+
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof {},
+
+    #[error("Self-excluded until {until}")]
+    SelfExcluded { until: Timestamp },
+
+    #[error("Deposit limit per {window} exceeded: remaining {remaining:?}")]
+    DepositLimitExceeded { window: String, remaining: Uint128 },
+
+    #[error("Invalid payment: expected {expected:?}, received {received:?}")]
+    InvalidPayment { expected: Uint128, received: Uint128 },
+
+    #[error("Already subscribed")]
+    AlreadySubscribed {},
+
+    #[error("Not subscribed")]
+    NotSubscribed {},
+
+    #[error("Claims of the round expire at {expires_at}")]
+    ClaimsNotExpired { expires_at: Timestamp },
+
+    #[error("Redeeming LTT is not enabled")]
+    RedeemDisabled {},
 }
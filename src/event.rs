@@ -1,4 +1,6 @@
-use cosmwasm_std::{attr, Response, Uint128};
+use cosmwasm_std::{attr, Coin, Response, Timestamp, Uint128};
+
+use crate::state::{PausableOperation, Role, WeightedValidator};
 
 pub trait Event {
     /// Append attributes to response
@@ -10,6 +12,7 @@ pub struct InvestedEvent<'a> {
     pub round: u32,
     pub who: &'a str,
     pub amount: Uint128,
+    pub denom: &'a str,
 }
 
 impl<'a> Event for InvestedEvent<'a> {
@@ -18,6 +21,270 @@ impl<'a> Event for InvestedEvent<'a> {
         rsp.attributes.push(attr("round", self.round.to_string()));
         rsp.attributes.push(attr("who", self.who));
         rsp.attributes.push(attr("amount", self.amount.to_string()));
+        rsp.attributes.push(attr("denom", self.denom));
+    }
+}
+
+/// Sponsor actions, the funds go to the prize only
+pub struct SponsoredEvent<'a> {
+    pub round: u32,
+    pub sponsor: &'a str,
+    pub amount: Uint128,
+    pub message: &'a str,
+}
+
+impl<'a> Event for SponsoredEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "Sponsored"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
+        rsp.attributes.push(attr("sponsor", self.sponsor));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+        rsp.attributes.push(attr("message", self.message));
+    }
+}
+
+/// Buy tickets actions
+pub struct TicketsBoughtEvent<'a> {
+    pub round: u32,
+    pub who: &'a str,
+    pub first_ticket: u64,
+    pub count: u64,
+}
+
+impl<'a> Event for TicketsBoughtEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "TicketsBought"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("first_ticket", self.first_ticket.to_string()));
+        rsp.attributes.push(attr("count", self.count.to_string()));
+    }
+}
+
+pub struct LottoTicketBoughtEvent<'a> {
+    pub round: u32,
+    pub who: &'a str,
+    pub ticket: u64,
+    pub numbers: &'a [u8],
+}
+
+impl<'a> Event for LottoTicketBoughtEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "LottoTicketBought"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("ticket", self.ticket.to_string()));
+        rsp.attributes.push(attr("numbers", join_numbers(self.numbers)));
+    }
+}
+
+pub struct LottoDrawnEvent<'a> {
+    pub round: u32,
+    pub numbers: &'a [u8],
+    pub rollover: Uint128,
+}
+
+impl<'a> Event for LottoDrawnEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "LottoDrawn"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
+        rsp.attributes.push(attr("numbers", join_numbers(self.numbers)));
+        rsp.attributes.push(attr("rollover", self.rollover.to_string()));
+    }
+}
+
+fn join_numbers(numbers: &[u8]) -> String {
+    numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(",")
+}
+
+pub struct ScratchChainCommittedEvent<'a> {
+    pub anchor: &'a str,
+    pub length: u64,
+}
+
+impl<'a> Event for ScratchChainCommittedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "ScratchChainCommitted"));
+        rsp.attributes.push(attr("anchor", self.anchor));
+        rsp.attributes.push(attr("length", self.length.to_string()));
+    }
+}
+
+/// A scratch card bought, its outcome is known once the link of the play is revealed
+pub struct ScratchPlayedEvent<'a> {
+    pub who: &'a str,
+    pub play: u64,
+}
+
+impl<'a> Event for ScratchPlayedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "ScratchPlayed"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("play", self.play.to_string()));
+    }
+}
+
+/// Stake of a scratch card given back, its link was not revealed in time
+pub struct ScratchRefundedEvent<'a> {
+    pub who: &'a str,
+    pub play: u64,
+    pub amount: Uint128,
+}
+
+impl<'a> Event for ScratchRefundedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "ScratchRefunded"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("play", self.play.to_string()));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+    }
+}
+
+pub struct LottoChainCommittedEvent<'a> {
+    pub anchor: &'a str,
+    pub length: u64,
+}
+
+impl<'a> Event for LottoChainCommittedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "LottoChainCommitted"));
+        rsp.attributes.push(attr("anchor", self.anchor));
+        rsp.attributes.push(attr("length", self.length.to_string()));
+    }
+}
+
+/// Seed of a lotto round, its ticket sales are closed
+pub struct LottoSeedRevealedEvent<'a> {
+    pub round: u32,
+    pub seed: &'a str,
+}
+
+impl<'a> Event for LottoSeedRevealedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "LottoSeedRevealed"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
+        rsp.attributes.push(attr("seed", self.seed));
+    }
+}
+
+/// Outcome of a scratch card, `multiplier` is 0 for a losing card
+pub struct ScratchedEvent<'a> {
+    pub who: &'a str,
+    pub play: u64,
+    pub preimage: &'a str,
+    pub multiplier: u64,
+    pub prize: Uint128,
+}
+
+impl<'a> Event for ScratchedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "Scratched"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("play", self.play.to_string()));
+        rsp.attributes.push(attr("preimage", self.preimage));
+        rsp.attributes.push(attr("multiplier", self.multiplier.to_string()));
+        rsp.attributes.push(attr("prize", self.prize.to_string()));
+    }
+}
+
+pub struct PausedEvent<'a> {
+    pub who: &'a str,
+    pub reason: &'a str,
+    pub operations: &'a [PausableOperation],
+}
+
+impl<'a> Event for PausedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        let operations = self
+            .operations
+            .iter()
+            .map(|o| format!("{:?}", o).to_lowercase())
+            .collect::<Vec<_>>();
+        rsp.attributes.push(attr("action", "Paused"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("reason", self.reason));
+        rsp.attributes.push(attr("operations", operations.join(",")));
+    }
+}
+
+pub struct UnpausedEvent<'a> {
+    pub who: &'a str,
+}
+
+impl<'a> Event for UnpausedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "Unpaused"));
+        rsp.attributes.push(attr("who", self.who));
+    }
+}
+
+/// Role granted or revoked
+pub struct RoleUpdatedEvent<'a> {
+    pub action: &'a str,
+    pub role: Role,
+    pub addr: &'a str,
+}
+
+impl<'a> Event for RoleUpdatedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", self.action));
+        rsp.attributes.push(attr("role", self.role.as_str()));
+        rsp.attributes.push(attr("addr", self.addr));
+    }
+}
+
+/// Lottery instance created by the factory
+pub struct LotteryCreatedEvent<'a> {
+    pub address: &'a str,
+    pub template: &'a str,
+    pub label: &'a str,
+}
+
+impl<'a> Event for LotteryCreatedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "LotteryCreated"));
+        rsp.attributes.push(attr("address", self.address));
+        rsp.attributes.push(attr("template", self.template));
+        rsp.attributes.push(attr("label", self.label));
+    }
+}
+
+/// Round cancelled by governance, its investments are claimable again
+pub struct RoundCancelledEvent {
+    pub round: u32,
+    pub refunds: u64,
+    pub burned: Uint128, // LTT of the refunded investments
+    pub subscribers_entered: u32,
+}
+
+impl Event for RoundCancelledEvent {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "RoundCancelled"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
+        rsp.attributes.push(attr("refunds", self.refunds.to_string()));
+        rsp.attributes.push(attr("burned", self.burned.to_string()));
+        if self.subscribers_entered > 0 {
+            let entered = self.subscribers_entered.to_string();
+            rsp.attributes.push(attr("subscribers_entered", entered));
+        }
+    }
+}
+
+/// Sweep of a claim left past its claim window
+pub struct ClaimExpiredEvent<'a> {
+    pub round: u32,
+    pub who: &'a str,
+    pub amount: &'a Coin,
+    pub to: &'a str, // "pot" or the treasury address
+}
+
+impl<'a> Event for ClaimExpiredEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "ClaimExpired"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+        rsp.attributes.push(attr("to", self.to));
     }
 }
 
@@ -47,13 +314,471 @@ pub struct ClosedInvestmentEvent<'a> {
     pub first_winner: &'a str,
     pub second_winner: &'a str,
     pub winner_hash: &'a str,
+    pub reinvested: Uint128, // prizes entered in the next round
+    pub vested: Uint128,
+    pub subscribers_entered: u32,
 }
 
 impl<'a> Event for ClosedInvestmentEvent<'a> {
     fn add_attributes(&self, rsp: &mut Response) {
         rsp.attributes.push(attr("action", "ClosedInvestment"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
         rsp.attributes.push(attr("first_winner", self.first_winner.to_string()));
         rsp.attributes.push(attr("second_winner", self.second_winner.to_string()));
         rsp.attributes.push(attr("winner_hash", self.winner_hash.to_string()));
+        if !self.reinvested.is_zero() {
+            rsp.attributes.push(attr("reinvested", self.reinvested.to_string()));
+        }
+        if !self.vested.is_zero() {
+            rsp.attributes.push(attr("vested", self.vested.to_string()));
+        }
+        if self.subscribers_entered > 0 {
+            let entered = self.subscribers_entered.to_string();
+            rsp.attributes.push(attr("subscribers_entered", entered));
+        }
+    }
+}
+
+/// Failed prize payout, moved to the claimable balance
+pub struct PayoutFailedEvent<'a> {
+    pub who: &'a str,
+    pub amount: &'a Coin,
+    pub reason: &'a str,
+}
+
+impl<'a> Event for PayoutFailedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "PayoutFailed"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+        rsp.attributes.push(attr("reason", self.reason));
+    }
+}
+
+/// Claim prize actions
+pub struct PrizeClaimedEvent<'a> {
+    pub who: &'a str,
+    pub amount: &'a [Coin],
+}
+
+impl<'a> Event for PrizeClaimedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        let amount = self.amount.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        rsp.attributes.push(attr("action", "PrizeClaimed"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("amount", amount.join(",")));
+    }
+}
+
+/// Withdraw fees actions
+pub struct FeesWithdrawnEvent<'a> {
+    pub to: &'a str,
+    pub amount: &'a [Coin],
+}
+
+impl<'a> Event for FeesWithdrawnEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        let amount = self.amount.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        rsp.attributes.push(attr("action", "FeesWithdrawn"));
+        rsp.attributes.push(attr("to", self.to));
+        rsp.attributes.push(attr("amount", amount.join(",")));
+    }
+}
+
+/// Unbond principal actions (no-loss mode), undelegated with `batch`
+pub struct UnbondedEvent<'a> {
+    pub who: &'a str,
+    pub amount: Uint128,
+    pub batch: u64,
+}
+
+impl<'a> Event for UnbondedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "Unbonded"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+        rsp.attributes.push(attr("batch", self.batch.to_string()));
+    }
+}
+
+/// Batch of unbonds undelegated, `release_at` is an estimate until the chain sets it
+pub struct BatchUndelegatedEvent {
+    pub batch: u64,
+    pub amount: Uint128,
+    pub release_at: u64,
+}
+
+impl Event for BatchUndelegatedEvent {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "BatchUndelegated"));
+        rsp.attributes.push(attr("batch", self.batch.to_string()));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+        rsp.attributes.push(attr("release_at", self.release_at.to_string()));
+    }
+}
+
+/// Unbonding completion time set by the chain (no-loss mode)
+pub struct UnbondingScheduledEvent<'a> {
+    pub batch: u64,
+    pub validator: &'a str,
+    pub release_at: u64,
+}
+
+impl<'a> Event for UnbondingScheduledEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "UnbondingScheduled"));
+        rsp.attributes.push(attr("batch", self.batch.to_string()));
+        rsp.attributes.push(attr("validator", self.validator));
+        rsp.attributes.push(attr("release_at", self.release_at.to_string()));
+    }
+}
+
+/// Withdraw principal actions (no-loss mode)
+pub struct PrincipalWithdrawnEvent<'a> {
+    pub who: &'a str,
+    pub amount: Uint128,
+}
+
+impl<'a> Event for PrincipalWithdrawnEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "PrincipalWithdrawn"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+    }
+}
+
+/// Validator set changes (no-loss mode)
+pub struct ValidatorsUpdatedEvent<'a> {
+    pub validators: &'a [WeightedValidator],
+    pub removed: &'a [String],
+}
+
+impl<'a> Event for ValidatorsUpdatedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        let validators = self
+            .validators
+            .iter()
+            .map(|v| format!("{}:{}", v.address, v.weight))
+            .collect::<Vec<_>>();
+        rsp.attributes.push(attr("action", "ValidatorsUpdated"));
+        rsp.attributes.push(attr("validators", validators.join(",")));
+        rsp.attributes.push(attr("removed", self.removed.join(",")));
+    }
+}
+
+/// Accepted denom changes
+pub struct DenomUpdatedEvent<'a> {
+    pub denom: &'a str,
+    pub exchange_ratio: Option<u128>,
+}
+
+impl<'a> Event for DenomUpdatedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        let action = if self.exchange_ratio.is_some() { "DenomAdded" } else { "DenomRemoved" };
+        rsp.attributes.push(attr("action", action));
+        rsp.attributes.push(attr("denom", self.denom));
+        if let Some(ratio) = self.exchange_ratio {
+            rsp.attributes.push(attr("exchange_ratio", ratio.to_string()));
+        }
+    }
+}
+
+/// Ticket NFT collection instantiated for the lottery
+pub struct TicketNftInstantiatedEvent<'a> {
+    pub collection: &'a str,
+}
+
+impl<'a> Event for TicketNftInstantiatedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "TicketNftInstantiated"));
+        rsp.attributes.push(attr("ticket_nft", self.collection));
+    }
+}
+
+/// Scratch bankroll funded by the treasurer
+pub struct BankrollFundedEvent {
+    pub amount: Uint128,
+    pub bankroll: Uint128,
+}
+
+impl Event for BankrollFundedEvent {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "BankrollFunded"));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+        rsp.attributes
+            .push(attr("bankroll", self.bankroll.to_string()));
+    }
+}
+
+/// Scratch bankroll withdrawn by the treasurer
+pub struct BankrollWithdrawnEvent {
+    pub amount: Uint128,
+    pub bankroll: Uint128,
+}
+
+impl Event for BankrollWithdrawnEvent {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "BankrollWithdrawn"));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+        rsp.attributes
+            .push(attr("bankroll", self.bankroll.to_string()));
+    }
+}
+
+/// Factory template changes
+pub struct TemplateAddedEvent<'a> {
+    pub name: &'a str,
+    pub code_id: u64,
+}
+
+impl<'a> Event for TemplateAddedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "TemplateAdded"));
+        rsp.attributes.push(attr("name", self.name));
+        rsp.attributes
+            .push(attr("code_id", self.code_id.to_string()));
+    }
+}
+
+pub struct TemplateRemovedEvent<'a> {
+    pub name: &'a str,
+}
+
+impl<'a> Event for TemplateRemovedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "TemplateRemoved"));
+        rsp.attributes.push(attr("name", self.name));
+    }
+}
+
+/// Admin action sent to a page of the created lotteries, `last` is the next cursor
+pub struct ExecutedOnLotteriesEvent<'a> {
+    pub count: u64,
+    pub last: &'a str,
+}
+
+impl<'a> Event for ExecutedOnLotteriesEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "ExecutedOnLotteries"));
+        rsp.attributes.push(attr("count", self.count.to_string()));
+        rsp.attributes.push(attr("last", self.last));
+    }
+}
+
+/// Scheduled close which closed nothing: the round is not due, was given another period
+/// as nobody entered it, or waits for its lotto seed
+pub struct DueRoundsCheckedEvent {
+    pub extended: Option<u32>,
+    pub awaiting_seed: Option<u32>,
+}
+
+impl Event for DueRoundsCheckedEvent {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "DueRoundsChecked"));
+        rsp.attributes.push(attr("closed", "0"));
+        if let Some(round) = self.extended {
+            rsp.attributes.push(attr("extended", round.to_string()));
+        }
+        if let Some(round) = self.awaiting_seed {
+            rsp.attributes
+                .push(attr("awaiting_seed", round.to_string()));
+        }
+    }
+}
+
+/// Allowlist or blocklist written by the admin
+pub struct AccessListUpdatedEvent<'a> {
+    pub list: &'a str,
+    pub added: u64,
+    pub removed: u64,
+}
+
+impl<'a> Event for AccessListUpdatedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "AccessListUpdated"));
+        rsp.attributes.push(attr("list", self.list));
+        rsp.attributes.push(attr("added", self.added.to_string()));
+        rsp.attributes
+            .push(attr("removed", self.removed.to_string()));
+    }
+}
+
+pub struct AllowlistSetEvent<'a> {
+    pub enabled: bool,
+    pub merkle_root: &'a str,
+}
+
+impl<'a> Event for AllowlistSetEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "AllowlistSet"));
+        rsp.attributes
+            .push(attr("enabled", self.enabled.to_string()));
+        rsp.attributes.push(attr("merkle_root", self.merkle_root));
+    }
+}
+
+/// Player proven under the merkle root of the allowlist
+pub struct AllowlistJoinedEvent<'a> {
+    pub who: &'a str,
+}
+
+impl<'a> Event for AllowlistJoinedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "AllowlistJoined"));
+        rsp.attributes.push(attr("who", self.who));
+    }
+}
+
+/// Responsible play settings of a player
+pub struct SelfExclusionSetEvent<'a> {
+    pub who: &'a str,
+    pub until: Timestamp,
+}
+
+impl<'a> Event for SelfExclusionSetEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "SelfExclusionSet"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("until", self.until.to_string()));
+    }
+}
+
+pub struct DepositLimitSetEvent<'a> {
+    pub who: &'a str,
+    pub effective_at: Option<Timestamp>, // when the requested limits are looser
+}
+
+impl<'a> Event for DepositLimitSetEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "DepositLimitSet"));
+        rsp.attributes.push(attr("who", self.who));
+        if let Some(effective_at) = self.effective_at {
+            rsp.attributes
+                .push(attr("effective_at", effective_at.to_string()));
+        }
+    }
+}
+
+/// Claims of the balances set aside for a player
+pub struct ReferralRewardsClaimedEvent<'a> {
+    pub who: &'a str,
+    pub amount: Uint128,
+}
+
+impl<'a> Event for ReferralRewardsClaimedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes
+            .push(attr("action", "ReferralRewardsClaimed"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+    }
+}
+
+/// Subscription actions
+pub struct SubscribedEvent<'a> {
+    pub who: &'a str,
+    pub rounds: u32,
+    pub amount_per_round: Uint128,
+    pub start_round: u32,
+}
+
+impl<'a> Event for SubscribedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "Subscribed"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("rounds", self.rounds.to_string()));
+        rsp.attributes
+            .push(attr("amount_per_round", self.amount_per_round.to_string()));
+        rsp.attributes
+            .push(attr("start_round", self.start_round.to_string()));
+    }
+}
+
+pub struct UnsubscribedEvent<'a> {
+    pub who: &'a str,
+    pub refund: Uint128,
+}
+
+impl<'a> Event for UnsubscribedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "Unsubscribed"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("refund", self.refund.to_string()));
+    }
+}
+
+pub struct SubscriptionsProcessedEvent {
+    pub entered: u32,
+    pub done: bool, // every subscriber entered the current round
+}
+
+impl Event for SubscriptionsProcessedEvent {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes
+            .push(attr("action", "SubscriptionsProcessed"));
+        rsp.attributes
+            .push(attr("entered", self.entered.to_string()));
+        rsp.attributes.push(attr("done", self.done.to_string()));
+    }
+}
+
+pub struct AutoReinvestSetEvent<'a> {
+    pub who: &'a str,
+    pub enabled: bool,
+    pub percent: u8,
+}
+
+impl<'a> Event for AutoReinvestSetEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "AutoReinvestSet"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes
+            .push(attr("enabled", self.enabled.to_string()));
+        rsp.attributes
+            .push(attr("percent", self.percent.to_string()));
+    }
+}
+
+pub struct VestedClaimedEvent<'a> {
+    pub who: &'a str,
+    pub amount: Uint128,
+}
+
+impl<'a> Event for VestedClaimedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "VestedClaimed"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+    }
+}
+
+pub struct DividendsClaimedEvent<'a> {
+    pub who: &'a str,
+    pub amount: Uint128,
+}
+
+impl<'a> Event for DividendsClaimedEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "DividendsClaimed"));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("amount", self.amount.to_string()));
+    }
+}
+
+/// LTT burned for draw weight in the current round
+pub struct RedeemedForTicketEvent<'a> {
+    pub round: u32,
+    pub who: &'a str,
+    pub burned: Uint128,
+    pub weight: Uint128,
+}
+
+impl<'a> Event for RedeemedForTicketEvent<'a> {
+    fn add_attributes(&self, rsp: &mut Response) {
+        rsp.attributes.push(attr("action", "RedeemedForTicket"));
+        rsp.attributes.push(attr("round", self.round.to_string()));
+        rsp.attributes.push(attr("who", self.who));
+        rsp.attributes.push(attr("burned", self.burned.to_string()));
+        rsp.attributes.push(attr("weight", self.weight.to_string()));
     }
 }
@@ -0,0 +1,175 @@
+use cosmwasm_std::{
+    Addr, Api, BankMsg, Coin, DepsMut, Empty, Env, Order, Response, StdResult, Storage, Timestamp,
+    Uint128,
+};
+
+use crate::contract::{make_cw20_transfer_msg, CW20_DENOM_PREFIX};
+use crate::error::ContractError;
+use crate::event::{ClaimExpiredEvent, Event};
+use crate::fees::accrue_fee;
+use crate::state::{
+    ClaimExpiry, SweepTarget, CLAIMABLE, CLAIM_EXPIRY, CLAIM_ROUNDS, CONTRACT_INFO, CURRENT,
+    INVESTMENTS, ROUND_CLAIMS,
+};
+
+// claims swept by a call, the rest by the next ones
+const MAX_SWEEP: usize = 50;
+
+pub fn validate_claim_expiry(api: &dyn Api, expiry: &ClaimExpiry) -> Result<(), ContractError> {
+    if expiry.window == 0 {
+        return Err(ContractError::InvalidParams {});
+    }
+    if let SweepTarget::Treasury { address } = &expiry.sweep_to {
+        api.addr_validate(address)?;
+    }
+    Ok(())
+}
+
+/// End of the claim window of a round closed at `env`, when claims expire at all.
+pub fn claims_deadline(storage: &dyn Storage, env: &Env) -> StdResult<Option<Timestamp>> {
+    Ok(CLAIM_EXPIRY
+        .may_load(storage)?
+        .map(|expiry| env.block.time.plus_seconds(expiry.window)))
+}
+
+/// Make `amount` claimable by `addr`, owed by `round` so that it can expire.
+pub fn credit_claim(
+    storage: &mut dyn Storage,
+    round: u32,
+    addr: &Addr,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    CLAIMABLE.update(storage, (addr, denom), |c| -> StdResult<_> {
+        Ok(c.unwrap_or_default().checked_add(amount)?)
+    })?;
+    if CLAIM_EXPIRY.may_load(storage)?.is_some() {
+        ROUND_CLAIMS.update(
+            storage,
+            (round.to_string(), addr, denom),
+            |c| -> StdResult<_> { Ok(c.unwrap_or_default().checked_add(amount)?) },
+        )?;
+        CLAIM_ROUNDS.save(storage, (addr, round.to_string()), &Empty {})?;
+    }
+    Ok(())
+}
+
+/// Forget the rounds owing claims to `addr`, once they claimed everything.
+pub fn forget_claims(storage: &mut dyn Storage, addr: &Addr) -> StdResult<()> {
+    let rounds = CLAIM_ROUNDS
+        .prefix(addr)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for round in rounds {
+        let denoms = ROUND_CLAIMS
+            .prefix((round.clone(), addr))
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for denom in denoms {
+            ROUND_CLAIMS.remove(storage, (round.clone(), addr, denom.as_str()));
+        }
+        CLAIM_ROUNDS.remove(storage, (addr, round));
+    }
+    Ok(())
+}
+
+/// Sweep the claims of `round` left past its claim window into the current pot or the
+/// treasury. Anyone may call it, a call sweeps up to `MAX_SWEEP` claims.
+pub fn handle_sweep_expired(
+    deps: DepsMut,
+    env: Env,
+    round: u32,
+) -> Result<Response, ContractError> {
+    let expiry = CLAIM_EXPIRY
+        .may_load(deps.storage)?
+        .ok_or(ContractError::InvalidParams {})?;
+    let expires_at = INVESTMENTS
+        .may_load(deps.storage, round.to_string())?
+        .and_then(|i| i.claims_expire_at)
+        .ok_or(ContractError::InvalidRound { round })?;
+    if env.block.time < expires_at {
+        return Err(ContractError::ClaimsNotExpired { expires_at });
+    }
+
+    let claims = ROUND_CLAIMS
+        .sub_prefix(round.to_string())
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(MAX_SWEEP)
+        .collect::<StdResult<Vec<_>>>()?;
+    if claims.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    let mut swept = vec![];
+    for ((addr, denom), amount) in claims {
+        ROUND_CLAIMS.remove(deps.storage, (round.to_string(), &addr, denom.as_str()));
+        let remaining = ROUND_CLAIMS
+            .prefix((round.to_string(), &addr))
+            .keys(deps.storage, None, None, Order::Ascending)
+            .next();
+        if remaining.is_none() {
+            CLAIM_ROUNDS.remove(deps.storage, (&addr, round.to_string()));
+        }
+
+        // the claimable balance also holds what other rounds owe
+        let claimable = CLAIMABLE
+            .may_load(deps.storage, (&addr, denom.as_str()))?
+            .unwrap_or_default();
+        let amount = amount.min(claimable);
+        if amount == claimable {
+            CLAIMABLE.remove(deps.storage, (&addr, denom.as_str()));
+        } else {
+            CLAIMABLE.save(deps.storage, (&addr, denom.as_str()), &(claimable - amount))?;
+        }
+        if !amount.is_zero() {
+            swept.push((addr, Coin::new(amount.u128(), denom)));
+        }
+    }
+
+    let mut rsp = Response::new();
+    let to = match &expiry.sweep_to {
+        SweepTarget::Pot => {
+            // added to the prize like a sponsorship, it is not drawn. A pool of another
+            // denom may have no investors to draw, those claims go to the fees instead.
+            let contract = CONTRACT_INFO.load(deps.storage)?;
+            let current = CURRENT.load(deps.storage)?.round;
+            let mut investment = INVESTMENTS.load(deps.storage, current.to_string())?;
+            for (_, coin) in swept.iter() {
+                if coin.denom == contract.use_denom {
+                    investment.swept_amount = investment.swept_amount.checked_add(coin.amount)?;
+                } else {
+                    accrue_fee(deps.storage, &coin.denom, coin.amount)?;
+                }
+            }
+            INVESTMENTS.save(deps.storage, current.to_string(), &investment)?;
+            "pot".to_string()
+        }
+        SweepTarget::Treasury { address } => {
+            for (_, coin) in swept.iter() {
+                rsp = match coin.denom.strip_prefix(CW20_DENOM_PREFIX) {
+                    Some(token) => rsp.add_message(make_cw20_transfer_msg(
+                        token,
+                        address.clone(),
+                        coin.amount,
+                    )?),
+                    None => rsp.add_message(BankMsg::Send {
+                        to_address: address.clone(),
+                        amount: vec![coin.clone()],
+                    }),
+                };
+            }
+            address.clone()
+        }
+    };
+
+    for (addr, coin) in swept.iter() {
+        ClaimExpiredEvent {
+            round,
+            who: addr.as_str(),
+            amount: coin,
+            to: &to,
+        }
+        .add_attributes(&mut rsp);
+    }
+    Ok(rsp)
+}
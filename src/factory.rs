@@ -0,0 +1,170 @@
+use cosmwasm_std::{
+    DepsMut, Env, MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg,
+};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::event::{
+    Event, ExecutedOnLotteriesEvent, LotteryCreatedEvent, TemplateAddedEvent,
+    TemplateRemovedEvent,
+};
+use crate::helpers::FsLotteryContract;
+use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::nft::parse_instantiated_address;
+use crate::roles::ensure_role;
+use crate::state::{
+    LotteryEntry, LotteryTemplate, Role, FACTORY, LOTTERIES, LOTTERY_TEMPLATES, PENDING_LOTTERY,
+};
+
+pub const INSTANTIATE_LOTTERY_REPLY_ID: u64 = 2;
+
+// bounds the messages of a single ExecuteOnLotteries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+fn ensure_factory(storage: &dyn Storage) -> Result<(), ContractError> {
+    if !FACTORY.may_load(storage)?.unwrap_or_default() {
+        return Err(ContractError::FactoryDisabled {});
+    }
+    Ok(())
+}
+
+/// Add a template to create lotteries from. It names the owner of those lotteries, who
+/// withdraws their fees, while the factory keeps the roles to manage them.
+pub fn handle_add_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    code_id: u64,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    ensure_factory(deps.storage)?;
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if name.is_empty() {
+        return Err(ContractError::InvalidParams {});
+    }
+    match &msg.owner {
+        Some(owner) => deps.api.addr_validate(owner)?,
+        None => return Err(ContractError::TemplateWithoutOwner {}),
+    };
+    LOTTERY_TEMPLATES.save(deps.storage, &name, &LotteryTemplate { code_id, msg })?;
+
+    let mut rsp = Response::new();
+    TemplateAddedEvent {
+        name: &name,
+        code_id,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+pub fn handle_remove_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    ensure_factory(deps.storage)?;
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if !LOTTERY_TEMPLATES.has(deps.storage, &name) {
+        return Err(ContractError::UnknownTemplate { name });
+    }
+    LOTTERY_TEMPLATES.remove(deps.storage, &name);
+
+    let mut rsp = Response::new();
+    TemplateRemovedEvent { name: &name }.add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// Instantiate a lottery from a template. The owner of the template becomes the owner and
+/// the admin of the new instance, the factory registers it when the instantiation replies.
+pub fn handle_create_lottery(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    template: String,
+    label: String,
+) -> Result<Response, ContractError> {
+    ensure_factory(deps.storage)?;
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let lottery_template = LOTTERY_TEMPLATES
+        .may_load(deps.storage, &template)?
+        .ok_or_else(|| ContractError::UnknownTemplate {
+            name: template.clone(),
+        })?;
+
+    PENDING_LOTTERY.save(
+        deps.storage,
+        &LotteryEntry {
+            template,
+            label: label.clone(),
+            created_at: env.block.time,
+        },
+    )?;
+    let msg = FsLotteryContract::instantiate(
+        lottery_template.code_id,
+        &lottery_template.msg,
+        label,
+        lottery_template.msg.owner.clone(),
+    )?;
+
+    Ok(Response::new().add_submessage(SubMsg::reply_on_success(msg, INSTANTIATE_LOTTERY_REPLY_ID)))
+}
+
+pub fn handle_instantiate_lottery_reply(
+    deps: DepsMut,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&parse_instantiated_address(msg)?)?;
+    let entry = PENDING_LOTTERY.load(deps.storage)?;
+    PENDING_LOTTERY.remove(deps.storage);
+    LOTTERIES.save(deps.storage, &address, &entry)?;
+
+    let mut rsp = Response::new();
+    LotteryCreatedEvent {
+        address: address.as_str(),
+        template: &entry.template,
+        label: &entry.label,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// Send an admin action to a page of the registered lotteries, e.g. to update their
+/// denoms or validators at once. The `last` attribute is the cursor of the next page.
+pub fn handle_execute_on_lotteries(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    ensure_factory(deps.storage)?;
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    let lotteries = LOTTERIES
+        .keys(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let msgs = lotteries
+        .iter()
+        .map(|addr| FsLotteryContract(addr.clone()).call(msg.clone()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let last = lotteries.last().map(|a| a.to_string()).unwrap_or_default();
+    let mut rsp = Response::new().add_messages(msgs);
+    ExecutedOnLotteriesEvent {
+        count: lotteries.len() as u64,
+        last: &last,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
@@ -0,0 +1,41 @@
+use cosmwasm_std::{Coin, DepsMut, MessageInfo, Order, Response, StdResult, Storage, Uint128};
+
+use crate::contract::make_send_msgs;
+use crate::error::ContractError;
+use crate::event::{Event, FeesWithdrawnEvent};
+use crate::roles::ensure_role;
+use crate::state::{Role, FEES};
+
+/// Add `amount` of `denom` to the fees a treasurer may withdraw.
+pub fn accrue_fee(storage: &mut dyn Storage, denom: &str, amount: Uint128) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    FEES.update(storage, denom, |fee| -> StdResult<_> {
+        Ok(fee.unwrap_or_default().checked_add(amount)?)
+    })?;
+    Ok(())
+}
+
+/// Send all the fees collected to the treasurer calling.
+pub fn handle_withdraw_fees(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Treasurer)?;
+    let amount = FEES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin::new(amount.u128(), denom)))
+        .collect::<StdResult<Vec<_>>>()?;
+    if amount.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    for coin in amount.iter() {
+        FEES.remove(deps.storage, coin.denom.as_str());
+    }
+
+    let mut rsp = Response::new().add_messages(make_send_msgs(info.sender.as_str(), &amount)?);
+    FeesWithdrawnEvent {
+        to: info.sender.as_str(),
+        amount: &amount,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
@@ -5,7 +5,7 @@ use cosmwasm_std::{
     to_binary, Addr, CosmosMsg, CustomQuery, Querier, QuerierWrapper, StdResult, WasmMsg, WasmQuery,
 };
 
-use crate::msg::{ExecuteMsg, QueryMsg, InfoResponse};
+use crate::msg::{ExecuteMsg, InfoResponse, InstantiateMsg, QueryMsg};
 
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
@@ -18,6 +18,22 @@ impl FsLotteryContract {
         self.0.clone()
     }
 
+    /// Message instantiating a new lottery from `code_id`
+    pub fn instantiate(
+        code_id: u64,
+        msg: &InstantiateMsg,
+        label: String,
+        admin: Option<String>,
+    ) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Instantiate {
+            admin,
+            code_id,
+            msg: to_binary(msg)?,
+            funds: vec![],
+            label,
+        })
+    }
+
     pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
         let msg = to_binary(&msg.into())?;
         Ok(WasmMsg::Execute {
@@ -1,22 +1,46 @@
 #[cfg(test)]
 mod tests {
+    use crate::error::ContractError;
     use crate::helpers::FsLotteryContract;
-    use crate::msg::InstantiateMsg;
-    use cosmwasm_std::{Addr, Coin, Empty, Uint128};
-    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, SudoMsg as LotterySudoMsg};
+    use crate::queries::{CurrentRoundResponse, DenomsResponse, LotteriesResponse, RolesResponse};
+    use crate::state::{AcceptedDenom, StakingConfig, WeightedValidator};
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{
+        coins, to_binary, Addr, Coin, Decimal, Empty, Timestamp, Uint128, Validator,
+    };
+    use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+    use cw_multi_test::{
+        App, AppBuilder, Contract, ContractWrapper, Executor, StakingInfo, StakingSudo, SudoMsg,
+    };
 
     pub fn contract_template() -> Box<dyn Contract<Empty>> {
         let contract = ContractWrapper::new(
             crate::contract::execute,
             crate::contract::instantiate,
             crate::queries::query,
+        )
+        .with_reply(crate::contract::reply)
+        .with_sudo(crate::contract::sudo);
+        Box::new(contract)
+    }
+
+    pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            cw20_base::contract::execute,
+            cw20_base::contract::instantiate,
+            cw20_base::contract::query,
         );
         Box::new(contract)
     }
 
-    const USER: &str = "USER";
-    const ADMIN: &str = "ADMIN";
+    const USER: &str = "user";
+    const ADMIN: &str = "admin";
     const NATIVE_DENOM: &str = "denom";
+    const STAKING_DENOM: &str = "cony";
+    const VALIDATOR: &str = "validator";
+    // completion time of every undelegation in the staking module, 2022-09-27T14:00:00Z
+    const UNBOND_COMPLETION: u64 = 1664287200;
 
     fn mock_app() -> App {
         AppBuilder::new().build(|router, _, storage| {
@@ -34,11 +58,8 @@ mod tests {
         })
     }
 
-    fn proper_instantiate() -> (App, FsLotteryContract) {
-        let mut app = mock_app();
-        let fs_lottery_id = app.store_code(contract_template());
-
-        let msg = InstantiateMsg { 
+    fn lottery_msg() -> InstantiateMsg {
+        InstantiateMsg { 
             use_denom: "cony".to_string(),
             exchange_ratio: 10, 
             min_exchange_amount: 200000000u32, 
@@ -48,7 +69,29 @@ mod tests {
             token_name: "lottery".to_string(), 
             token_symbol: "LTT".to_string(), 
             token_decimals: 6u8, 
-        };
+            no_loss: None,
+            accepted_denoms: None,
+            tickets: None,
+            ticket_nft: None,
+            lotto: None,
+            scratch: None,
+            round_duration: None,
+            limits: None,
+            referral_share: None,
+            vesting: None,
+            claim_expiry: None,
+            dividend_share: None,
+            redeem_rate: None,
+            owner: None,
+            factory: None,
+        }
+    }
+
+    fn proper_instantiate() -> (App, FsLotteryContract) {
+        let mut app = mock_app();
+        let fs_lottery_id = app.store_code(contract_template());
+
+        let msg = lottery_msg();
         let fs_lottery_contract_addr = app
             .instantiate_contract(
                 fs_lottery_id,
@@ -64,4 +107,372 @@ mod tests {
 
         (app, cw_template_contract)
     }
+
+    fn mock_staking_app() -> App {
+        AppBuilder::new().build(|router, api, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(USER), coins(1000, STAKING_DENOM))
+                .unwrap();
+            router
+                .staking
+                .setup(
+                    storage,
+                    StakingInfo {
+                        bonded_denom: STAKING_DENOM.to_string(),
+                        unbonding_time: 60,
+                        apr: Decimal::percent(10),
+                    },
+                )
+                .unwrap();
+            router
+                .staking
+                .add_validator(
+                    api,
+                    storage,
+                    &mock_env().block,
+                    Validator {
+                        address: VALIDATOR.to_string(),
+                        commission: Decimal::zero(),
+                        max_commission: Decimal::one(),
+                        max_change_rate: Decimal::one(),
+                    },
+                )
+                .unwrap();
+        })
+    }
+
+    #[test]
+    fn no_loss_round() {
+        let mut app = mock_staking_app();
+        let fs_lottery_id = app.store_code(contract_template());
+
+        let msg = InstantiateMsg {
+            use_denom: STAKING_DENOM.to_string(),
+            no_loss: Some(StakingConfig {
+                validators: vec![WeightedValidator {
+                    address: VALIDATOR.to_string(),
+                    weight: 1,
+                }],
+                unbonding_period: 60,
+            }),
+            ..lottery_msg()
+        };
+        let contract_addr = app
+            .instantiate_contract(
+                fs_lottery_id,
+                Addr::unchecked(ADMIN),
+                &msg,
+                &[],
+                "test",
+                None,
+            )
+            .unwrap();
+
+        // principal is delegated
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr.clone(),
+            &ExecuteMsg::Invest { referrer: None },
+            &coins(1000, STAKING_DENOM),
+        )
+        .unwrap();
+        let delegation = app
+            .wrap()
+            .query_delegation(contract_addr.clone(), VALIDATOR)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Coin::new(1000, STAKING_DENOM), delegation.amount);
+
+        // a year of rewards at 10% apr is drawn
+        app.update_block(|block| block.time = block.time.plus_seconds(365 * 24 * 60 * 60));
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &ExecuteMsg::CloseInvestment {},
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(USER, STAKING_DENOM).unwrap();
+        assert_eq!(Uint128::new(100 * 60 / 100 + 100 * 20 / 100), balance.amount);
+
+        // principal is returned after unbonding, with the batch anyone undelegates
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr.clone(),
+            &ExecuteMsg::Unbond {
+                amount: Uint128::new(1000),
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr.clone(),
+            &ExecuteMsg::Rebalance {},
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr.clone(),
+            &ExecuteMsg::WithdrawPrincipal {},
+            &[],
+        )
+        .unwrap_err();
+
+        // released after the completion time the chain reports for the undelegation
+        app.update_block(|block| block.time = Timestamp::from_seconds(UNBOND_COMPLETION + 1));
+        app.sudo(SudoMsg::Staking(StakingSudo::ProcessQueue {})).unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr,
+            &ExecuteMsg::WithdrawPrincipal {},
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(USER, STAKING_DENOM).unwrap();
+        assert_eq!(Uint128::new(1080), balance.amount);
+    }
+
+    #[test]
+    fn invest_cw20() {
+        let mut app = mock_app();
+        let cw20_id = app.store_code(contract_cw20());
+        let fs_lottery_id = app.store_code(contract_template());
+
+        let token_addr = app
+            .instantiate_contract(
+                cw20_id,
+                Addr::unchecked(ADMIN),
+                &cw20_base::msg::InstantiateMsg {
+                    name: "cony token".to_string(),
+                    symbol: "CONYT".to_string(),
+                    decimals: 6,
+                    initial_balances: vec![Cw20Coin {
+                        address: USER.to_string(),
+                        amount: Uint128::new(1000),
+                    }],
+                    mint: None,
+                    marketing: None,
+                },
+                &[],
+                "cw20",
+                None,
+            )
+            .unwrap();
+
+        let msg = InstantiateMsg {
+            accepted_denoms: Some(vec![AcceptedDenom {
+                denom: format!("cw20:{}", token_addr),
+                exchange_ratio: 10,
+            }]),
+            ..lottery_msg()
+        };
+        let contract_addr = app
+            .instantiate_contract(
+                fs_lottery_id,
+                Addr::unchecked(ADMIN),
+                &msg,
+                &[],
+                "test",
+                None,
+            )
+            .unwrap();
+
+        // invest through the cw20 hook
+        app.execute_contract(
+            Addr::unchecked(USER),
+            token_addr.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: contract_addr.to_string(),
+                amount: Uint128::new(1000),
+                msg: to_binary(&ReceiveMsg::Invest { referrer: None }).unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // prizes are paid in the token, the owner cut is withdrawn in it too
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &ExecuteMsg::CloseInvestment {},
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr,
+            &ExecuteMsg::WithdrawFees {},
+            &[],
+        )
+        .unwrap();
+        let balance = |app: &App, who: &str| -> Uint128 {
+            let res: BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    token_addr.clone(),
+                    &Cw20QueryMsg::Balance {
+                        address: who.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        };
+        assert_eq!(Uint128::new(1000 * 60 / 100 + 1000 * 20 / 100), balance(&app, USER));
+        assert_eq!(Uint128::new(1000 * 2 / 100), balance(&app, ADMIN));
+    }
+
+    #[test]
+    fn factory_is_opt_in() {
+        let (mut app, lottery) = proper_instantiate();
+        let msg = ExecuteMsg::AddTemplate {
+            name: "daily".to_string(),
+            code_id: 1,
+            msg: Box::new(InstantiateMsg {
+                owner: Some(ADMIN.to_string()),
+                ..lottery_msg()
+            }),
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(ADMIN), lottery.addr(), &msg, &[])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ContractError>(),
+            Some(ContractError::FactoryDisabled {})
+        ));
+    }
+
+    #[test]
+    fn factory_manages_lotteries() {
+        let mut app = mock_app();
+        let code_id = app.store_code(contract_template());
+        let msg = InstantiateMsg {
+            factory: Some(true),
+            ..lottery_msg()
+        };
+        let factory = app
+            .instantiate_contract(code_id, Addr::unchecked(ADMIN), &msg, &[], "factory", None)
+            .unwrap();
+        let factory = FsLotteryContract(factory);
+
+        let template = InstantiateMsg {
+            owner: Some(ADMIN.to_string()),
+            ..lottery_msg()
+        };
+        let msg = ExecuteMsg::AddTemplate {
+            name: "daily".to_string(),
+            code_id,
+            msg: Box::new(template),
+        };
+        app.execute_contract(Addr::unchecked(ADMIN), factory.addr(), &msg, &[])
+            .unwrap();
+        for label in ["daily-1", "daily-2"] {
+            let msg = ExecuteMsg::CreateLottery {
+                template: "daily".to_string(),
+                label: label.to_string(),
+            };
+            app.execute_contract(Addr::unchecked(ADMIN), factory.addr(), &msg, &[])
+                .unwrap();
+        }
+
+        let msg = QueryMsg::Lotteries {
+            start_after: None,
+            limit: None,
+        };
+        let res: LotteriesResponse = app.wrap().query_wasm_smart(factory.addr(), &msg).unwrap();
+        assert_eq!(2, res.lotteries.len());
+
+        // the template owner owns every instance, admin actions reach them through the factory
+        let msg = ExecuteMsg::ExecuteOnLotteries {
+            msg: Box::new(ExecuteMsg::AddDenom {
+                denom: "brown".to_string(),
+                exchange_ratio: 2,
+            }),
+            start_after: None,
+            limit: None,
+        };
+        app.execute_contract(Addr::unchecked(ADMIN), factory.addr(), &msg, &[])
+            .unwrap();
+        for lottery in res.lotteries {
+            let roles: RolesResponse = app
+                .wrap()
+                .query_wasm_smart(lottery.address.clone(), &QueryMsg::Roles {})
+                .unwrap();
+            assert_eq!(ADMIN, roles.owner);
+            let denoms: DenomsResponse = app
+                .wrap()
+                .query_wasm_smart(lottery.address, &QueryMsg::Denoms {})
+                .unwrap();
+            assert_eq!("brown", denoms.denoms[1].denom);
+        }
+    }
+
+    #[test]
+    fn sudo_closes_due_rounds() {
+        let mut app = mock_staking_app();
+        let fs_lottery_id = app.store_code(contract_template());
+        let msg = InstantiateMsg {
+            round_duration: Some(3600),
+            ..lottery_msg()
+        };
+        let contract_addr = app
+            .instantiate_contract(
+                fs_lottery_id,
+                Addr::unchecked(ADMIN),
+                &msg,
+                &[],
+                "test",
+                None,
+            )
+            .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr.clone(),
+            &ExecuteMsg::Invest { referrer: None },
+            &coins(1000, STAKING_DENOM),
+        )
+        .unwrap();
+        let current_round = |app: &App| -> u32 {
+            let res: CurrentRoundResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::CurrentRound {})
+                .unwrap();
+            res.round
+        };
+
+        // the round is left open until its deadline
+        app.wasm_sudo(contract_addr.clone(), &LotterySudoMsg::CloseDueRounds {})
+            .unwrap();
+        assert_eq!(1, current_round(&app));
+
+        app.update_block(|block| block.time = block.time.plus_seconds(3600));
+        app.wasm_sudo(contract_addr.clone(), &LotterySudoMsg::CloseDueRounds {})
+            .unwrap();
+        assert_eq!(2, current_round(&app));
+        let balance = app.wrap().query_balance(USER, STAKING_DENOM).unwrap();
+        assert_eq!(Uint128::new(1000 * 60 / 100 + 1000 * 20 / 100), balance.amount);
+
+        // a cancelled round is refunded through the claimable balance
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr.clone(),
+            &ExecuteMsg::Invest { referrer: None },
+            &coins(800, STAKING_DENOM),
+        )
+        .unwrap();
+        app.wasm_sudo(contract_addr.clone(), &LotterySudoMsg::ForceCancel { round: 2 })
+            .unwrap();
+        assert_eq!(3, current_round(&app));
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr,
+            &ExecuteMsg::ClaimPrize {},
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(USER, STAKING_DENOM).unwrap();
+        assert_eq!(Uint128::new(800), balance.amount);
+    }
 }
@@ -1,10 +1,31 @@
 pub mod contract;
+mod access;
 pub mod queries;
+mod dividends;
 mod error;
 mod event;
+mod expiry;
+mod factory;
+mod fees;
 pub mod helpers;
 pub mod integration_tests;
+mod limits;
+mod lotto;
 pub mod msg;
+mod nft;
+mod pause;
+mod redeem;
+mod referral;
+mod reinvest;
+mod responsible;
+mod roles;
+mod scratch;
+mod sponsor;
+mod staking;
+mod subscription;
+mod sudo;
 pub mod state;
+mod tickets;
+mod vesting;
 
 pub use crate::error::ContractError;
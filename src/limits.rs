@@ -0,0 +1,95 @@
+use cosmwasm_std::{Addr, Storage, Uint128};
+
+use crate::error::ContractError;
+use crate::state::{InvestLimits, Investment, OverpaymentPolicy, INVESTORS, INVEST_LIMITS};
+
+pub fn validate_invest_limits(limits: &InvestLimits) -> Result<(), ContractError> {
+    let min = limits.min_invest.unwrap_or_default();
+    for cap in [limits.max_invest_per_address_per_round, limits.max_pot]
+        .iter()
+        .flatten()
+    {
+        if cap.is_zero() || *cap < min {
+            return Err(ContractError::InvalidParams {});
+        }
+    }
+    Ok(())
+}
+
+/// Split an investment of `received` into the amount the round accepts and the amount to
+/// refund. An amount over a cap fails unless the overflow policy refunds the excess.
+pub fn apply_invest_limits(
+    storage: &dyn Storage,
+    investment: &Investment,
+    sender: &Addr,
+    received: Uint128,
+) -> Result<(Uint128, Uint128), ContractError> {
+    limit_entry(storage, investment, sender, received, true)
+}
+
+/// Check an entry of `amount` that cannot be split, e.g. tickets, fits the limits. An
+/// amount over a cap fails whatever the overflow policy.
+pub fn ensure_invest_limits(
+    storage: &dyn Storage,
+    investment: &Investment,
+    sender: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    limit_entry(storage, investment, sender, amount, false).map(|_| ())
+}
+
+fn limit_entry(
+    storage: &dyn Storage,
+    investment: &Investment,
+    sender: &Addr,
+    received: Uint128,
+    splittable: bool,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let limits = match INVEST_LIMITS.may_load(storage)? {
+        Some(limits) => limits,
+        None => return Ok((received, Uint128::zero())),
+    };
+    let min = limits.min_invest.unwrap_or_default();
+    if received < min {
+        return Err(ContractError::BelowMinInvest { min, received });
+    }
+
+    let mut accepted = received;
+    let mut exceeded = None;
+    if let Some(cap) = limits.max_invest_per_address_per_round {
+        let invested = INVESTORS
+            .may_load(storage, (investment.round.to_string(), sender))?
+            .unwrap_or_default();
+        let available = cap.saturating_sub(invested);
+        if available < accepted {
+            accepted = available;
+            exceeded = Some(ContractError::AddressCapExceeded {
+                available,
+                received,
+            });
+        }
+    }
+    if let Some(cap) = limits.max_pot {
+        let available = cap.saturating_sub(investment.total_amount);
+        if available < accepted {
+            accepted = available;
+            exceeded = Some(ContractError::PotCapExceeded {
+                available,
+                received,
+            });
+        }
+    }
+
+    // what is left under the caps must still make a valid investment
+    match exceeded {
+        Some(err)
+            if limits.overflow == OverpaymentPolicy::Reject
+                || !splittable
+                || accepted.is_zero()
+                || accepted < min =>
+        {
+            Err(err)
+        }
+        _ => Ok((accepted, received - accepted)),
+    }
+}
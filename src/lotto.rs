@@ -0,0 +1,322 @@
+use std::ops::Range;
+
+use cosmwasm_std::{Binary, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult, Uint128};
+use sha2::{Digest, Sha256};
+
+use crate::access::ensure_allowed;
+use crate::contract::{mint_token, must_pay};
+use crate::error::ContractError;
+use crate::event::{
+    Event, InvestedEvent, LottoChainCommittedEvent, LottoSeedRevealedEvent, LottoTicketBoughtEvent,
+};
+use crate::limits::ensure_invest_limits;
+use crate::nft::ticket_owner;
+use crate::referral::bind_referrer;
+use crate::responsible::record_deposit;
+use crate::roles::ensure_role;
+use crate::state::{
+    LottoChain, LottoConfig, LottoResult, Role, TierResult, Winner, CONTRACT_INFO, CURRENT,
+    INVESTMENTS, INVESTORS, LOTTO_CHAIN, LOTTO_CONFIG, LOTTO_NUMBERS, LOTTO_RESULTS,
+    LOTTO_ROLLOVER, LOTTO_SEEDS, OWNED_TICKETS, TICKETS,
+};
+
+pub fn validate_lotto_config(config: &LottoConfig, owner_ratio: u8) -> Result<(), ContractError> {
+    if config.pick == 0 || config.pick > config.max_number || config.price.is_zero() {
+        return Err(ContractError::InvalidParams {});
+    }
+    let mut total = owner_ratio as u32;
+    for (i, tier) in config.tiers.iter().enumerate() {
+        if tier.matches == 0 || tier.matches > config.pick {
+            return Err(ContractError::InvalidParams {});
+        }
+        if config.tiers[..i].iter().any(|t| t.matches == tier.matches) {
+            return Err(ContractError::InvalidParams {});
+        }
+        total += tier.ratio as u32;
+    }
+    if config.tiers.is_empty() || total > 100 {
+        return Err(ContractError::InvalidParams {});
+    }
+    Ok(())
+}
+
+pub fn handle_buy_lotto_ticket(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut numbers: Vec<u8>,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = LOTTO_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::LottoDisabled {})?;
+
+    // `pick` distinct numbers in 1..=max_number
+    numbers.sort_unstable();
+    numbers.dedup();
+    if numbers.len() != config.pick as usize
+        || numbers.iter().any(|n| *n == 0 || *n > config.max_number)
+    {
+        return Err(ContractError::InvalidNumbers {});
+    }
+
+    ensure_allowed(deps.storage, &info.sender)?;
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let paid = must_pay(&info, &contract.use_denom)?;
+    if paid != config.price {
+        return Err(ContractError::InvalidPayment {
+            expected: config.price,
+            received: paid,
+        });
+    }
+    let round = CURRENT.load(deps.storage)?.round;
+    // once the seed is out the numbers are known
+    if LOTTO_SEEDS.has(deps.storage, round.to_string()) {
+        return Err(ContractError::LottoSalesClosed { round });
+    }
+    let mut investment = INVESTMENTS
+        .may_load(deps.storage, round.to_string())?
+        .ok_or(ContractError::InvalidRound { round })?;
+    ensure_invest_limits(deps.storage, &investment, &info.sender, paid)?;
+    record_deposit(deps.storage, &env, &info.sender, paid)?;
+    bind_referrer(deps.storage, deps.api, &info.sender, referrer)?;
+
+    let ticket = investment.ticket_count + 1;
+    TICKETS.save(deps.storage, (round.to_string(), ticket), &info.sender)?;
+    OWNED_TICKETS.save(
+        deps.storage,
+        (round.to_string(), &info.sender, ticket),
+        &Empty {},
+    )?;
+    LOTTO_NUMBERS.save(deps.storage, (round.to_string(), ticket), &numbers)?;
+    investment.ticket_count = ticket;
+    investment.total_amount = investment.total_amount.checked_add(paid)?;
+    INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
+    INVESTORS.update(
+        deps.storage,
+        (round.to_string(), &info.sender),
+        |a| -> StdResult<_> { Ok(a.unwrap_or_default().checked_add(paid)?) },
+    )?;
+
+    let mut rsp = Response::new();
+    let exchange_amount = paid
+        .checked_mul(Uint128::new(contract.exchange_ratio))
+        .map_err(|e| ContractError::CustomError { val: e.to_string() })?;
+    mint_token(deps, &env, &info.sender, exchange_amount)?;
+
+    LottoTicketBoughtEvent {
+        round,
+        who: info.sender.as_str(),
+        ticket,
+        numbers: &numbers,
+    }
+    .add_attributes(&mut rsp);
+    InvestedEvent {
+        round,
+        who: info.sender.as_str(),
+        amount: paid,
+        denom: &contract.use_denom,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Commit to a new hash chain of `length` links by its last one, `sha256^length(secret)`.
+pub fn handle_commit_lotto_chain(
+    deps: DepsMut,
+    info: MessageInfo,
+    anchor: Binary,
+    length: u64,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if LOTTO_CONFIG.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::LottoDisabled {});
+    }
+    if anchor.len() != 32 || length == 0 {
+        return Err(ContractError::InvalidParams {});
+    }
+    LOTTO_CHAIN.save(
+        deps.storage,
+        &LottoChain {
+            tip: anchor.clone(),
+            remaining: length,
+        },
+    )?;
+
+    let mut rsp = Response::new();
+    LottoChainCommittedEvent {
+        anchor: &anchor.to_base64(),
+        length,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Reveal the next link of the chain as the seed of the current round. It ends the ticket
+/// sales of the round, which is drawn from it at close. The link is fixed by the chain, so
+/// the numbers cannot be picked at close, but the admin who made the chain knows them
+/// while tickets sell and is trusted not to play. A round without tickets is not revealed,
+/// it could never be drawn.
+pub fn handle_reveal_lotto_seed(
+    deps: DepsMut,
+    info: MessageInfo,
+    preimage: Binary,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if LOTTO_CONFIG.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::LottoDisabled {});
+    }
+    let round = CURRENT.load(deps.storage)?.round;
+    if LOTTO_SEEDS.has(deps.storage, round.to_string()) {
+        return Err(ContractError::LottoSalesClosed { round });
+    }
+    let investment = INVESTMENTS
+        .may_load(deps.storage, round.to_string())?
+        .ok_or(ContractError::InvalidRound { round })?;
+    if investment.ticket_count == 0 {
+        return Err(ContractError::NoInvestors { round });
+    }
+    let mut chain = LOTTO_CHAIN
+        .may_load(deps.storage)?
+        .filter(|c| c.remaining > 0)
+        .ok_or(ContractError::LottoChainExhausted {})?;
+    if Sha256::digest(preimage.as_slice())[..] != chain.tip[..] {
+        return Err(ContractError::InvalidPreimage {});
+    }
+    chain.tip = preimage.clone();
+    chain.remaining -= 1;
+    LOTTO_CHAIN.save(deps.storage, &chain)?;
+    LOTTO_SEEDS.save(deps.storage, round.to_string(), &preimage)?;
+
+    let mut rsp = Response::new();
+    LottoSeedRevealedEvent {
+        round,
+        seed: &preimage.to_base64(),
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Seed of a round, derived from the link revealed for it and the tickets sold.
+pub fn round_seed(link: &[u8], round: u32, ticket_count: u64) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(link)
+        .chain_update(round.to_be_bytes())
+        .chain_update(ticket_count.to_be_bytes())
+        .finalize()
+        .to_vec()
+}
+
+/// Derive `pick` distinct winning numbers in 1..=max_number from the seed, sorted.
+pub fn draw_numbers(seed: &[u8], pick: u8, max_number: u8) -> Vec<u8> {
+    let mut numbers = Vec::with_capacity(pick as usize);
+    let mut nonce = 0u32;
+    while numbers.len() < pick as usize {
+        let hash = Sha256::new()
+            .chain_update(seed)
+            .chain_update(nonce.to_be_bytes())
+            .finalize();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hash[..8]);
+        let n = (u64::from_be_bytes(bytes) % max_number as u64) as u8 + 1;
+        if !numbers.contains(&n) {
+            numbers.push(n);
+        }
+        nonce += 1;
+    }
+    numbers.sort_unstable();
+    numbers
+}
+
+/// Positions of the top tier prizes, the most matches, among the winners of `draw_lotto`.
+pub fn top_tier_winners(result: &LottoResult) -> Range<usize> {
+    let top = match result.tiers.iter().map(|t| t.matches).max() {
+        Some(top) => top,
+        None => return 0..0,
+    };
+    let mut start = 0;
+    for tier in result.tiers.iter() {
+        if tier.matches == top {
+            return start..start + tier.winners as usize;
+        }
+        start += tier.winners as usize;
+    }
+    0..0
+}
+
+/// Draw the winning numbers of a round from its revealed seed and split the pot by tiers.
+/// The pot is the ticket sales plus the rollover of the last round; the owner share is
+/// taken from the sales only. Shares of tiers without winners, and the rounding dust, roll
+/// over to the next round.
+pub fn draw_lotto(
+    deps: DepsMut,
+    config: &LottoConfig,
+    round: u32,
+    ticket_count: u64,
+    sales: Uint128,
+) -> Result<(Vec<Winner>, LottoResult), ContractError> {
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let pot = sales.checked_add(LOTTO_ROLLOVER.may_load(deps.storage)?.unwrap_or_default())?;
+
+    let link = LOTTO_SEEDS
+        .may_load(deps.storage, round.to_string())?
+        .ok_or(ContractError::LottoSeedNotRevealed { round })?;
+    let seed = round_seed(&link, round, ticket_count);
+    let numbers = draw_numbers(&seed, config.pick, config.max_number);
+
+    // winning ticket ids per tier
+    let mut tier_tickets = vec![vec![]; config.tiers.len()];
+    for item in LOTTO_NUMBERS
+        .prefix(round.to_string())
+        .range(deps.storage, None, None, Order::Ascending)
+    {
+        let (ticket, picked) = item?;
+        let matches = picked.iter().filter(|n| numbers.contains(n)).count() as u8;
+        if let Some(i) = config.tiers.iter().position(|t| t.matches == matches) {
+            tier_tickets[i].push(ticket);
+        }
+    }
+
+    let mut winners = vec![];
+    let mut tiers = vec![];
+    let mut paid = Uint128::zero();
+    for (tier, tickets) in config.tiers.iter().zip(tier_tickets) {
+        let mut prize = Uint128::zero();
+        if !tickets.is_empty() {
+            let share = pot.multiply_ratio(tier.ratio as u128, 100u128);
+            prize = share.multiply_ratio(1u128, tickets.len() as u128);
+            for ticket in &tickets {
+                winners.push(Winner {
+                    addr: ticket_owner(deps.as_ref(), round, *ticket)?,
+                    amount: prize,
+                });
+            }
+            paid += prize * Uint128::from(tickets.len() as u128);
+        }
+        tiers.push(TierResult {
+            matches: tier.matches,
+            winners: tickets.len() as u64,
+            prize,
+        });
+    }
+    let owner_amount = sales.multiply_ratio(contract.owner_ratio as u128, 100u128);
+    winners.push(Winner {
+        addr: contract.owner.to_string(),
+        amount: owner_amount,
+    });
+
+    let rollover = pot.checked_sub(paid)?.checked_sub(owner_amount)?;
+    LOTTO_ROLLOVER.save(deps.storage, &rollover)?;
+    let result = LottoResult {
+        seed: Binary::from(seed),
+        numbers,
+        tiers,
+        rollover,
+    };
+    LOTTO_RESULTS.save(deps.storage, round.to_string(), &result)?;
+
+    Ok((winners, result))
+}
@@ -1,7 +1,13 @@
-use cosmwasm_std::{Uint128};
+use cosmwasm_std::{Binary, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{
+    AcceptedDenom, ClaimExpiry, InvestLimits, LottoConfig, PausableOperation, Role, ScratchConfig,
+    StakingConfig, TicketConfig, TicketNftConfig, VestingConfig, WeightedValidator,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub use_denom: String,     // denoms to treat
@@ -14,12 +20,34 @@ pub struct InstantiateMsg {
     pub token_name: String,
     pub token_symbol: String, 
     pub token_decimals: u8,
+
+    pub no_loss: Option<StakingConfig>, // enables no-loss mode when set
+    pub accepted_denoms: Option<Vec<AcceptedDenom>>, // besides use_denom, cw20 as "cw20:<addr>"
+    pub tickets: Option<TicketConfig>, // enables ticket mode when set
+    pub ticket_nft: Option<TicketNftConfig>, // mints tickets as cw721 NFTs in ticket mode
+    pub lotto: Option<LottoConfig>, // enables pick-N lotto mode when set
+    pub scratch: Option<ScratchConfig>, // enables instant-win scratch cards when set
+    pub round_duration: Option<u64>, // seconds a round runs before it can be closed by sudo
+    pub limits: Option<InvestLimits>, // caps on the entries of a round, without other denoms
+    pub referral_share: Option<u8>, // percent of the owner cut paid to referrers
+    pub vesting: Option<VestingConfig>, // vests large first and lotto top tier prizes when set
+    pub claim_expiry: Option<ClaimExpiry>, // unclaimed prizes and refunds expire when set
+    pub dividend_share: Option<u8>, // percent of the owner cut paid to LTT holders
+    pub redeem_rate: Option<Uint128>, // LTT burned per unit of bonus draw weight
+    pub owner: Option<String>, // owner of the lottery, the sender when not set
+    pub factory: Option<bool>, // enables creating lotteries from templates when true
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    Invest {},
+    Invest { referrer: Option<String> }, // the referrer is bound on the first investment
+    Receive(Cw20ReceiveMsg),
+    BuyTickets { count: u64, referrer: Option<String> },
+    BuyLottoTicket { numbers: Vec<u8>, referrer: Option<String> },
+    // the lotto seed of a round is the next link of a committed hash chain
+    CommitLottoChain { anchor: Binary, length: u64 },
+    RevealLottoSeed { preimage: Binary }, // ends the ticket sales of the current round
     CloseInvestment {},
     // DistributeReward {},
     // RequestExchangeToken { amount: Uint128 },
@@ -27,6 +55,73 @@ pub enum ExecuteMsg {
     // StartExchange {},
     // EndExchange { round: Uint128 },
     TransferToken { to: String, amount: Uint128 },
+    ClaimPrize {},
+    AddDenom { denom: String, exchange_ratio: u128 },
+    RemoveDenom { denom: String },
+    // no-loss mode
+    Unbond { amount: Uint128 }, // undelegated with the next batch
+    WithdrawPrincipal {},
+    UpdateValidators { validators: Vec<WeightedValidator> },
+    Rebalance {}, // anyone, also undelegates the batch of unbonds once per epoch
+    // scratch cards
+    Scratch {},
+    CommitScratchChain { anchor: Binary, length: u64 },
+    RevealScratch { preimages: Vec<Binary> }, // settles the plays waiting, in order
+    RefundScratch {}, // anyone, once a play waited too long for its reveal
+    FundBankroll {},
+    WithdrawBankroll { amount: Uint128 },
+    // emergency pause, `operations` defaults to all of them
+    Pause { reason: String, operations: Option<Vec<PausableOperation>> },
+    Unpause {},
+    // roles
+    GrantRole { role: Role, addr: String },
+    RevokeRole { role: Role, addr: String },
+    // factory mode, a template names the owner of the lotteries created from it
+    AddTemplate { name: String, code_id: u64, msg: Box<InstantiateMsg> },
+    RemoveTemplate { name: String },
+    CreateLottery { template: String, label: String },
+    ExecuteOnLotteries { msg: Box<ExecuteMsg>, start_after: Option<String>, limit: Option<u32> },
+    // access lists
+    UpdateAllowlist { add: Vec<String>, remove: Vec<String> },
+    UpdateBlocklist { add: Vec<String>, remove: Vec<String> },
+    SetAllowlist { enabled: bool, merkle_root: Option<Binary> },
+    JoinAllowlist { proof: Vec<Binary> },
+    // responsible play
+    SetSelfExclusion { until: Timestamp },
+    SetDepositLimit { per_day: Option<Uint128>, per_week: Option<Uint128> },
+    // referrals
+    ClaimReferralRewards {},
+    // adds to the prize of the current round without entering it
+    Sponsor { round: u32, message: Option<String> },
+    // entries prepaid for the coming rounds
+    Subscribe { rounds: u32, amount_per_round: Uint128, referrer: Option<String> },
+    Unsubscribe {},
+    ProcessSubscriptions { limit: Option<u32> },
+    // prizes of the sender partly enter the next round instead of being sent
+    SetAutoReinvest { enabled: bool, percent: u8 },
+    ClaimVested {},
+    // moves the claims of a round left past the claim window, anyone may call it
+    SweepExpired { round: u32 },
+    ClaimDividends {},
+    // burns LTT for draw weight in the current round, free tickets in ticket mode
+    RedeemForTicket { amount: Uint128 },
+    // sends the owner cut collected by the rounds to the treasurer
+    WithdrawFees {},
+}
+
+// messages the chain sends to the contract without a signer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    CloseDueRounds {},
+    ForceCancel { round: u32 },
+}
+
+// cw20 hook messages, sent in Cw20ReceiveMsg.msg
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Invest { referrer: Option<String> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -40,6 +135,37 @@ pub enum QueryMsg {
     InvestResult { round: u32, },
     TotalTokenSupply {},
     TokenBalance { who: String },
+    Claimable { who: String },
+    Denoms {},
+    Pools { round: u32 },
+    PoolInvestors { round: u32, denom: String, start_after: Option<String>, limit: Option<u32> },
+    Tickets { round: u32, owner: String, start_after: Option<u64>, limit: Option<u32> },
+    LottoTicket { round: u32, ticket: u64 },
+    LottoResult { round: u32 },
+    LottoChain {},
+    StakingInfo {},
+    Principal { who: String },
+    Delegations {},
+    UnbondingQueue {},
+    ScratchInfo {},
+    PauseInfo {},
+    Roles {},
+    Templates { start_after: Option<String>, limit: Option<u32> },
+    Lotteries { start_after: Option<String>, limit: Option<u32> },
+    Allowlist { start_after: Option<String>, limit: Option<u32> },
+    Blocklist { start_after: Option<String>, limit: Option<u32> },
+    PlayerLimits { addr: String },
+    ReferralStats { addr: String },
+    Sponsors { round: u32 },
+    Subscriptions { start_after: Option<String>, limit: Option<u32> },
+    AutoReinvest { addr: String },
+    Vesting { addr: String },
+    Dividends { addr: String },
+    BonusWeights { round: u32 },
+    Fees {},
+    // LTT as of the start of block `height`
+    BalanceAtHeight { addr: String, height: u64 },
+    TotalSupplyAtHeight { height: u64 },
     // CurrentExchangeRound {},
     // CurrentExchangeRequesters { start_after: Option<String>, limit: Option<u32> },
     // ExchangeResult { round: Uint128 },
@@ -63,4 +189,11 @@ pub struct InfoResponse {
     pub token_name: String,
     pub token_symbol: String, 
     pub token_decimals: u8,
+
+    pub ticket_price: Option<Uint128>,
+    pub ticket_nft: Option<String>,
+    pub lotto: Option<LottoConfig>,
+    pub limits: Option<InvestLimits>,
+    pub vesting: Option<VestingConfig>,
+    pub claim_expiry: Option<ClaimExpiry>,
 }
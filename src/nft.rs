@@ -0,0 +1,157 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    to_binary, Addr, Deps, Reply, StdError, StdResult, SubMsg, SubMsgResult, WasmMsg,
+};
+
+use crate::state::{TICKETS, TICKET_NFT};
+
+// Messages of the ticket cw721 collection. They follow cw721-base (0.16+) and the
+// metadata of cw721-metadata-onchain, so either can be used as the child code.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw721InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub minter: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: TicketMetadata,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721QueryMsg {
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnerOfResponse {
+    pub owner: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TicketMetadata {
+    pub attributes: Vec<Trait>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Trait {
+    pub display_type: Option<String>,
+    pub trait_type: String,
+    pub value: String,
+}
+
+/// Token id of a ticket, "<round>-<ticket id>"
+pub fn ticket_token_id(round: u32, ticket: u64) -> String {
+    format!("{}-{}", round, ticket)
+}
+
+pub fn instantiate_collection_msg(
+    code_id: u64,
+    name: String,
+    symbol: String,
+    minter: &Addr,
+    reply_id: u64,
+) -> StdResult<SubMsg> {
+    let msg = Cw721InstantiateMsg {
+        name: name.clone(),
+        symbol,
+        minter: minter.to_string(),
+    };
+    Ok(SubMsg::reply_on_success(
+        WasmMsg::Instantiate {
+            admin: Some(minter.to_string()),
+            code_id,
+            msg: to_binary(&msg)?,
+            funds: vec![],
+            label: name,
+        },
+        reply_id,
+    ))
+}
+
+pub fn mint_ticket_msg(
+    collection: &Addr,
+    owner: &Addr,
+    round: u32,
+    ticket: u64,
+) -> StdResult<WasmMsg> {
+    let msg = Cw721ExecuteMsg::Mint {
+        token_id: ticket_token_id(round, ticket),
+        owner: owner.to_string(),
+        token_uri: None,
+        extension: TicketMetadata {
+            attributes: vec![
+                Trait {
+                    display_type: None,
+                    trait_type: "round".to_string(),
+                    value: round.to_string(),
+                },
+                Trait {
+                    display_type: Some("number".to_string()),
+                    trait_type: "ticket".to_string(),
+                    value: ticket.to_string(),
+                },
+            ],
+        },
+    };
+    Ok(WasmMsg::Execute {
+        contract_addr: collection.to_string(),
+        msg: to_binary(&msg)?,
+        funds: vec![],
+    })
+}
+
+/// Address of the instantiated contract, read from the `instantiate` event.
+pub fn parse_instantiated_address(msg: Reply) -> StdResult<String> {
+    let res = match msg.result {
+        SubMsgResult::Ok(res) => res,
+        SubMsgResult::Err(err) => return Err(StdError::generic_err(err)),
+    };
+    res.events
+        .iter()
+        .filter(|e| e.ty == "instantiate")
+        .flat_map(|e| e.attributes.iter())
+        .find(|a| a.key == "_contract_address")
+        .map(|a| a.value.clone())
+        .ok_or_else(|| StdError::generic_err("no instantiated contract address"))
+}
+
+/// Current owner of a ticket. With a ticket collection the holder of the NFT owns it,
+/// as tickets may be traded or gifted before the draw. A ticket whose NFT is not found,
+/// e.g. because it was burned, stays with its buyer so the round can still be closed. Any
+/// other failure of the query fails the lookup, the prize must not go to the buyer of a
+/// ticket sold on.
+pub fn ticket_owner(deps: Deps, round: u32, ticket: u64) -> StdResult<String> {
+    if let Some(collection) = TICKET_NFT.may_load(deps.storage)? {
+        let res: StdResult<OwnerOfResponse> = deps.querier.query_wasm_smart(
+            collection,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: ticket_token_id(round, ticket),
+                include_expired: None,
+            },
+        );
+        match res {
+            Ok(res) => return Ok(res.owner),
+            // the collection reports its error as text, see cw721-base `OwnerOf`
+            Err(StdError::GenericErr { msg }) if msg.contains("not found") => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(TICKETS
+        .load(deps.storage, (round.to_string(), ticket))?
+        .to_string())
+}
@@ -0,0 +1,95 @@
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, Storage};
+
+use crate::error::ContractError;
+use crate::event::{Event, PausedEvent, UnpausedEvent};
+use crate::msg::ExecuteMsg;
+use crate::roles::ensure_role;
+use crate::state::{PausableOperation, PauseInfo, Role, PAUSE};
+
+/// Operation of an execute message that a pause can stop. Claims, withdrawals and
+/// admin messages always go through.
+pub fn operation_of(msg: &ExecuteMsg) -> Option<PausableOperation> {
+    match msg {
+        ExecuteMsg::Invest { .. }
+        | ExecuteMsg::Receive(_)
+        | ExecuteMsg::BuyTickets { .. }
+        | ExecuteMsg::BuyLottoTicket { .. }
+        | ExecuteMsg::Scratch {}
+        | ExecuteMsg::Sponsor { .. }
+        | ExecuteMsg::Subscribe { .. }
+        | ExecuteMsg::RedeemForTicket { .. } => Some(PausableOperation::Invest),
+        ExecuteMsg::CloseInvestment {} => Some(PausableOperation::Close),
+        ExecuteMsg::TransferToken { .. } => Some(PausableOperation::Transfer),
+        _ => None,
+    }
+}
+
+pub fn ensure_not_paused(
+    storage: &dyn Storage,
+    operation: PausableOperation,
+) -> Result<(), ContractError> {
+    match PAUSE.may_load(storage)? {
+        Some(pause) if pause.operations.contains(&operation) => Err(ContractError::Paused {
+            reason: pause.reason,
+        }),
+        _ => Ok(()),
+    }
+}
+
+pub fn handle_pause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    reason: String,
+    operations: Option<Vec<PausableOperation>>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Guardian)?;
+    let operations = operations.unwrap_or_else(|| {
+        vec![
+            PausableOperation::Invest,
+            PausableOperation::Close,
+            PausableOperation::Transfer,
+        ]
+    });
+    let operations = operations.into_iter().fold(vec![], |mut ops, op| {
+        if !ops.contains(&op) {
+            ops.push(op);
+        }
+        ops
+    });
+    if operations.is_empty() {
+        return Err(ContractError::InvalidParams {});
+    }
+
+    // a new pause replaces the operations of the current one
+    PAUSE.save(
+        deps.storage,
+        &PauseInfo {
+            reason: reason.clone(),
+            operations: operations.clone(),
+            paused_by: info.sender.clone(),
+            paused_at: env.block.time,
+        },
+    )?;
+
+    let mut rsp = Response::new();
+    PausedEvent {
+        who: info.sender.as_str(),
+        reason: &reason,
+        operations: &operations,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+pub fn handle_unpause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Guardian)?;
+    PAUSE.remove(deps.storage);
+
+    let mut rsp = Response::new();
+    UnpausedEvent {
+        who: info.sender.as_str(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
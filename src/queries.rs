@@ -1,11 +1,23 @@
-use crate::msg::{InfoResponse, QueryMsg};
+use crate::dividends::pending_dividends;
+use crate::msg::{InfoResponse, InstantiateMsg, QueryMsg};
+use crate::responsible::{deposited_since, load_player_limits, DAY, WEEK};
+use crate::vesting::unlocked;
 use crate::state::{
-    Investor, Winner, BALANCES, CONTRACT_INFO, CURRENT, INVESTMENTS, INVESTORS, TOKEN_INFO,
+    AcceptedDenom, DepositLimit, Investor, PauseInfo, PendingDepositLimit, Pool, Role,
+    ScratchPrize, TierResult, UnbondBatch, ValidatorUnbonding, WeightedValidator, Winner,
+    ACCEPTED_DENOMS, ALLOWLIST, ALLOWLIST_CONFIG, AUTO_REINVEST, BALANCES, BLOCKLIST,
+    BONUS_WEIGHTS, CLAIMABLE, CLAIM_EXPIRY, CONTRACT_INFO, CURRENT, FEES, INVESTMENTS, INVESTORS,
+    INVEST_LIMITS, LOTTERIES, LOTTERY_TEMPLATES, LOTTO_CHAIN, LOTTO_CONFIG, LOTTO_NUMBERS,
+    LOTTO_RESULTS, LOTTO_SEEDS, OWNED_TICKETS, PAUSE, POOLS, POOL_INVESTORS, PRINCIPALS,
+    REFERRAL_STATS, REFERRERS, ROLES, SCRATCH_BANKROLL, SCRATCH_CHAIN, SCRATCH_CONFIG, SPONSORS,
+    STAKING, STAKING_CONFIG, SUBSCRIPTIONS, TICKETS, TICKET_CONFIG, TICKET_NFT, TOKEN_INFO,
+    UNBONDINGS, UNBONDING_QUEUE, UNBOND_BATCH, UNBOND_RELEASES, VESTINGS, VESTING_CONFIG,
 };
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, Env, Order, StdError, StdResult, Uint128,
+    entry_point, to_binary, Addr, Binary, Coin, Decimal, Deps, Empty, Env, Order, StdError,
+    StdResult, Timestamp, Uint128,
 };
-use cw_storage_plus::Bound;
+use cw_storage_plus::{Bound, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -13,7 +25,7 @@ const DEFAULT_LIMIT: u32 = 10;
 const MAX_LIMIT: u32 = 100;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Info {} => to_binary(&query_contract_info(deps)?),
         QueryMsg::CurrentRound {} => to_binary(&query_current_round(deps)?),
@@ -29,6 +41,62 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::InvestResult { round } => to_binary(&query_invest_result(deps, round)?),
         QueryMsg::TotalTokenSupply {} => to_binary(&query_token_total_supply(deps)?),
         QueryMsg::TokenBalance { who } => to_binary(&query_token_balance(deps, who)?),
+        QueryMsg::Claimable { who } => to_binary(&query_claimable(deps, who)?),
+        QueryMsg::Denoms {} => to_binary(&query_denoms(deps)?),
+        QueryMsg::Pools { round } => to_binary(&query_pools(deps, round)?),
+        QueryMsg::PoolInvestors {
+            round,
+            denom,
+            start_after,
+            limit,
+        } => to_binary(&query_pool_investors(deps, round, denom, start_after, limit)?),
+        QueryMsg::Tickets {
+            round,
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_tickets(deps, round, owner, start_after, limit)?),
+        QueryMsg::LottoTicket { round, ticket } => {
+            to_binary(&query_lotto_ticket(deps, round, ticket)?)
+        }
+        QueryMsg::LottoResult { round } => to_binary(&query_lotto_result(deps, round)?),
+        QueryMsg::LottoChain {} => to_binary(&query_lotto_chain(deps)?),
+        QueryMsg::StakingInfo {} => to_binary(&query_staking_info(deps)?),
+        QueryMsg::Principal { who } => to_binary(&query_principal(deps, who)?),
+        QueryMsg::Delegations {} => to_binary(&query_delegations(deps, env)?),
+        QueryMsg::UnbondingQueue {} => to_binary(&query_unbonding_queue(deps, env)?),
+        QueryMsg::ScratchInfo {} => to_binary(&query_scratch_info(deps)?),
+        QueryMsg::PauseInfo {} => to_binary(&query_pause_info(deps)?),
+        QueryMsg::Roles {} => to_binary(&query_roles(deps)?),
+        QueryMsg::Templates { start_after, limit } => {
+            to_binary(&query_templates(deps, start_after, limit)?)
+        }
+        QueryMsg::Lotteries { start_after, limit } => {
+            to_binary(&query_lotteries(deps, start_after, limit)?)
+        }
+        QueryMsg::Allowlist { start_after, limit } => {
+            to_binary(&query_allowlist(deps, start_after, limit)?)
+        }
+        QueryMsg::Blocklist { start_after, limit } => {
+            to_binary(&query_blocklist(deps, start_after, limit)?)
+        }
+        QueryMsg::PlayerLimits { addr } => to_binary(&query_player_limits(deps, env, addr)?),
+        QueryMsg::ReferralStats { addr } => to_binary(&query_referral_stats(deps, addr)?),
+        QueryMsg::Sponsors { round } => to_binary(&query_sponsors(deps, round)?),
+        QueryMsg::Subscriptions { start_after, limit } => {
+            to_binary(&query_subscriptions(deps, start_after, limit)?)
+        }
+        QueryMsg::AutoReinvest { addr } => to_binary(&query_auto_reinvest(deps, addr)?),
+        QueryMsg::Vesting { addr } => to_binary(&query_vesting(deps, env, addr)?),
+        QueryMsg::Dividends { addr } => to_binary(&query_dividends(deps, addr)?),
+        QueryMsg::BonusWeights { round } => to_binary(&query_bonus_weights(deps, round)?),
+        QueryMsg::Fees {} => to_binary(&query_fees(deps)?),
+        QueryMsg::BalanceAtHeight { addr, height } => {
+            to_binary(&query_balance_at_height(deps, addr, height)?)
+        }
+        QueryMsg::TotalSupplyAtHeight { height } => {
+            to_binary(&query_total_supply_at_height(deps, height)?)
+        }
     }
 }
 
@@ -44,6 +112,12 @@ fn query_contract_info(deps: Deps) -> StdResult<InfoResponse> {
         token_name: token.name.clone(),
         token_symbol: token.symbol.clone(),
         token_decimals: token.decimals,
+        ticket_price: TICKET_CONFIG.may_load(deps.storage)?.map(|t| t.price),
+        ticket_nft: TICKET_NFT.may_load(deps.storage)?.map(|a| a.to_string()),
+        lotto: LOTTO_CONFIG.may_load(deps.storage)?,
+        limits: INVEST_LIMITS.may_load(deps.storage)?,
+        vesting: VESTING_CONFIG.may_load(deps.storage)?,
+        claim_expiry: CLAIM_EXPIRY.may_load(deps.storage)?,
     })
 }
 
@@ -55,7 +129,13 @@ pub fn query_current_round(deps: Deps) -> StdResult<CurrentRoundResponse> {
 pub fn query_current_investment(deps: Deps) -> StdResult<CurrentInvestmentResponse> {
     let round = query_current_round(deps)?.round;
     let investment = INVESTMENTS.load(deps.storage, round.to_string())?;
-    Ok(CurrentInvestmentResponse { round: investment.round, total_amount: investment.total_amount })
+    Ok(CurrentInvestmentResponse {
+        round: investment.round,
+        total_amount: investment.total_amount,
+        deadline: investment.deadline,
+        sponsored_amount: investment.sponsored_amount,
+        swept_amount: investment.swept_amount,
+    })
 }
 
 pub fn query_current_investors(
@@ -64,7 +144,7 @@ pub fn query_current_investors(
     limit: Option<u32>,
 ) -> StdResult<InvestorsResponse> {
     let round = CURRENT.load(deps.storage)?.round;
-    return query_investors(deps, round, start_after, limit);
+    query_investors(deps, round, start_after, limit)
 }
 
 pub fn query_investors(
@@ -98,16 +178,21 @@ pub fn query_invest_result(deps: Deps, round: u32) -> StdResult<InvestResultResp
     if investment.in_progress {
         return Err(StdError::generic_err("in progress"));
     }
+    // a round drawn only in other pools has no winners of its own
     Ok(InvestResultResponse {
         round,
-        first_winner: investment.first_winner.unwrap(),
-        second_winner: investment.second_winner.unwrap(),
+        first_winner: investment
+            .first_winner
+            .ok_or_else(|| StdError::not_found("first winner"))?,
+        second_winner: investment
+            .second_winner
+            .ok_or_else(|| StdError::not_found("second winner"))?,
     })
 }
 
 pub fn query_token_total_supply(deps: Deps) -> StdResult<TotalSupplyResponse> {
     let supply = TOKEN_INFO.load(deps.storage)?.total_supply;
-    Ok(TotalSupplyResponse { supply: supply })
+    Ok(TotalSupplyResponse { supply })
 }
 
 pub fn query_token_balance(deps: Deps, who: String) -> StdResult<TokenBalanceResponse> {
@@ -116,6 +201,497 @@ pub fn query_token_balance(deps: Deps, who: String) -> StdResult<TokenBalanceRes
     Ok(TokenBalanceResponse { balance })
 }
 
+pub fn query_balance_at_height(
+    deps: Deps,
+    addr: String,
+    height: u64,
+) -> StdResult<TokenBalanceResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let balance = BALANCES
+        .may_load_at_height(deps.storage, &addr, height)?
+        .unwrap_or_default();
+    Ok(TokenBalanceResponse { balance })
+}
+
+pub fn query_total_supply_at_height(deps: Deps, height: u64) -> StdResult<TotalSupplyResponse> {
+    let supply = TOKEN_INFO
+        .may_load_at_height(deps.storage, height)?
+        .map(|token| token.total_supply)
+        .unwrap_or_default();
+    Ok(TotalSupplyResponse { supply })
+}
+
+pub fn query_claimable(deps: Deps, who: String) -> StdResult<ClaimableResponse> {
+    let who_addr = deps.api.addr_validate(&who)?;
+    let amount = CLAIMABLE
+        .prefix(&who_addr)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin::new(amount.u128(), denom)))
+        .collect::<StdResult<_>>()?;
+    Ok(ClaimableResponse { amount })
+}
+
+pub fn query_denoms(deps: Deps) -> StdResult<DenomsResponse> {
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let mut denoms = vec![AcceptedDenom {
+        denom: contract.use_denom,
+        exchange_ratio: contract.exchange_ratio,
+    }];
+    for item in ACCEPTED_DENOMS.range(deps.storage, None, None, Order::Ascending) {
+        let (denom, exchange_ratio) = item?;
+        denoms.push(AcceptedDenom {
+            denom,
+            exchange_ratio,
+        });
+    }
+    Ok(DenomsResponse { denoms })
+}
+
+pub fn query_pools(deps: Deps, round: u32) -> StdResult<PoolsResponse> {
+    let pools = POOLS
+        .prefix(round.to_string())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, pool)| pool))
+        .collect::<StdResult<_>>()?;
+    Ok(PoolsResponse { round, pools })
+}
+
+pub fn query_pool_investors(
+    deps: Deps,
+    round: u32,
+    denom: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<InvestorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let investors = POOL_INVESTORS
+        .prefix((round.to_string(), denom.as_str()))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(addr, amount)| Investor {
+                addr: addr.to_string(),
+                amount,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(InvestorsResponse { round, investors })
+}
+
+/// Tickets bought by `owner`. With a ticket collection these are the original purchases,
+/// the holder of a ticket NFT at the draw is the one who wins with it.
+pub fn query_tickets(
+    deps: Deps,
+    round: u32,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TicketsResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let tickets = OWNED_TICKETS
+        .prefix((round.to_string(), &owner_addr))
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    Ok(TicketsResponse {
+        round,
+        owner,
+        tickets,
+    })
+}
+
+/// The owner is the buyer of the ticket, see `query_tickets` for traded ticket NFTs.
+pub fn query_lotto_ticket(deps: Deps, round: u32, ticket: u64) -> StdResult<LottoTicketResponse> {
+    let numbers = LOTTO_NUMBERS.load(deps.storage, (round.to_string(), ticket))?;
+    let owner = TICKETS.load(deps.storage, (round.to_string(), ticket))?;
+    Ok(LottoTicketResponse {
+        round,
+        ticket,
+        owner: owner.to_string(),
+        numbers,
+    })
+}
+
+pub fn query_lotto_result(deps: Deps, round: u32) -> StdResult<LottoResultResponse> {
+    let result = LOTTO_RESULTS.load(deps.storage, round.to_string())?;
+    Ok(LottoResultResponse {
+        round,
+        seed: result.seed,
+        numbers: result.numbers,
+        tiers: result.tiers,
+        rollover: result.rollover,
+    })
+}
+
+pub fn query_lotto_chain(deps: Deps) -> StdResult<LottoChainResponse> {
+    let round = CURRENT.load(deps.storage)?.round;
+    let chain = LOTTO_CHAIN.may_load(deps.storage)?;
+    Ok(LottoChainResponse {
+        tip: chain.as_ref().map(|c| c.tip.clone()),
+        remaining: chain.map(|c| c.remaining).unwrap_or_default(),
+        sales_closed: LOTTO_SEEDS.has(deps.storage, round.to_string()),
+    })
+}
+
+pub fn query_staking_info(deps: Deps) -> StdResult<StakingInfoResponse> {
+    let config = STAKING_CONFIG
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("no-loss mode is not enabled"))?;
+    let total_staked = STAKING.load(deps.storage)?;
+    Ok(StakingInfoResponse {
+        validators: config.validators,
+        unbonding_period: config.unbonding_period,
+        total_staked,
+        unbond_batch: UNBOND_BATCH.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn query_principal(deps: Deps, who: String) -> StdResult<PrincipalResponse> {
+    let who_addr = deps.api.addr_validate(&who)?;
+    let principal = PRINCIPALS.may_load(deps.storage, &who_addr)?.unwrap_or_default();
+    let unbondings = UNBONDINGS
+        .may_load(deps.storage, &who_addr)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|u| {
+            Ok(PrincipalUnbonding {
+                amount: u.amount,
+                batch: u.batch,
+                release_at: UNBOND_RELEASES.may_load(deps.storage, u.batch)?,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PrincipalResponse { principal, unbondings })
+}
+
+pub fn query_delegations(deps: Deps, env: Env) -> StdResult<DelegationsResponse> {
+    let config = STAKING_CONFIG
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("no-loss mode is not enabled"))?;
+    let delegations = config
+        .validators
+        .into_iter()
+        .map(|v| {
+            let amount = deps
+                .querier
+                .query_delegation(env.contract.address.clone(), &v.address)?
+                .map(|d| d.amount.amount)
+                .unwrap_or_default();
+            Ok(ValidatorDelegation {
+                validator: v.address,
+                weight: v.weight,
+                amount,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(DelegationsResponse { delegations })
+}
+
+pub fn query_unbonding_queue(deps: Deps, env: Env) -> StdResult<UnbondingQueueResponse> {
+    let unbondings = UNBONDING_QUEUE
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|u| u.matures_at > env.block.time)
+        .collect();
+    Ok(UnbondingQueueResponse { unbondings })
+}
+
+pub fn query_scratch_info(deps: Deps) -> StdResult<ScratchInfoResponse> {
+    let config = SCRATCH_CONFIG
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("scratch cards are not enabled"))?;
+    let chain = SCRATCH_CHAIN.may_load(deps.storage)?;
+    Ok(ScratchInfoResponse {
+        price: config.price,
+        prizes: config.prizes,
+        bankroll: SCRATCH_BANKROLL.load(deps.storage)?,
+        tip: chain.as_ref().map(|c| c.tip.clone()),
+        remaining: chain.as_ref().map(|c| c.remaining).unwrap_or_default(),
+        plays: chain.as_ref().map(|c| c.plays).unwrap_or_default(),
+        pending: chain.map(|c| c.plays - c.settled).unwrap_or_default(),
+    })
+}
+
+pub fn query_pause_info(deps: Deps) -> StdResult<PauseInfoResponse> {
+    Ok(PauseInfoResponse {
+        pause: PAUSE.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_roles(deps: Deps) -> StdResult<RolesResponse> {
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let roles = Role::ALL
+        .iter()
+        .map(|role| {
+            let members = ROLES
+                .prefix(role.as_str())
+                .keys(deps.storage, None, None, Order::Ascending)
+                .map(|addr| addr.map(String::from))
+                .collect::<StdResult<_>>()?;
+            Ok(RoleMembers {
+                role: *role,
+                members,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(RolesResponse {
+        owner: contract.owner.to_string(),
+        roles,
+    })
+}
+
+pub fn query_templates(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TemplatesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let templates = LOTTERY_TEMPLATES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(name, template)| TemplateInfo {
+                name,
+                code_id: template.code_id,
+                msg: template.msg,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(TemplatesResponse { templates })
+}
+
+pub fn query_lotteries(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<LotteriesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    let lotteries = LOTTERIES
+        .range(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, entry)| LotteryInfo {
+                address: address.to_string(),
+                template: entry.template,
+                label: entry.label,
+                created_at: entry.created_at,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(LotteriesResponse { lotteries })
+}
+
+pub fn query_player_limits(
+    deps: Deps,
+    env: Env,
+    addr: String,
+) -> StdResult<PlayerLimitsResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let now = env.block.time;
+    let limits = load_player_limits(deps.storage, &addr, now)?.unwrap_or_default();
+    let deposited_day = deposited_since(deps.storage, &addr, now, DAY)?;
+    let deposited_week = deposited_since(deps.storage, &addr, now, WEEK)?;
+    let remaining = |limit: Option<Uint128>, deposited: Uint128| {
+        limit.map(|l| l.saturating_sub(deposited))
+    };
+
+    Ok(PlayerLimitsResponse {
+        excluded_until: limits.excluded_until.filter(|until| now < *until),
+        remaining_day: remaining(limits.deposit_limit.per_day, deposited_day),
+        remaining_week: remaining(limits.deposit_limit.per_week, deposited_week),
+        deposit_limit: limits.deposit_limit,
+        pending: limits.pending,
+        deposited_day,
+        deposited_week,
+    })
+}
+
+pub fn query_referral_stats(deps: Deps, addr: String) -> StdResult<ReferralStatsResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let stats = REFERRAL_STATS
+        .may_load(deps.storage, &addr)?
+        .unwrap_or_default();
+    Ok(ReferralStatsResponse {
+        referrer: REFERRERS.may_load(deps.storage, &addr)?.map(String::from),
+        referees: stats.referees,
+        earned: stats.earned,
+        claimable: stats.claimable,
+    })
+}
+
+pub fn query_sponsors(deps: Deps, round: u32) -> StdResult<SponsorsResponse> {
+    let investment = INVESTMENTS.load(deps.storage, round.to_string())?;
+    let sponsors = SPONSORS
+        .prefix(round.to_string())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(sponsor, s)| SponsorInfo {
+                sponsor: sponsor.to_string(),
+                amount: s.amount,
+                message: s.message,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(SponsorsResponse {
+        round,
+        total_amount: investment.sponsored_amount,
+        sponsors,
+    })
+}
+
+pub fn query_subscriptions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SubscriptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    let subscriptions = SUBSCRIPTIONS
+        .range(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(addr, s)| SubscriptionInfo {
+                addr: addr.to_string(),
+                amount_per_round: s.amount_per_round,
+                remaining_rounds: s.remaining_rounds,
+                start_round: s.start_round,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(SubscriptionsResponse { subscriptions })
+}
+
+pub fn query_auto_reinvest(deps: Deps, addr: String) -> StdResult<AutoReinvestResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let percent = AUTO_REINVEST.may_load(deps.storage, &addr)?;
+    Ok(AutoReinvestResponse {
+        enabled: percent.is_some(),
+        percent: percent.unwrap_or_default(),
+    })
+}
+
+pub fn query_vesting(deps: Deps, env: Env, addr: String) -> StdResult<VestingResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let schedules = VESTINGS
+        .prefix(&addr)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(round, s)| VestingInfo {
+                round,
+                claimable: unlocked(&s, env.block.time) - s.claimed,
+                total: s.total,
+                claimed: s.claimed,
+                start: s.start,
+                end: s.end,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(VestingResponse { schedules })
+}
+
+pub fn query_dividends(deps: Deps, addr: String) -> StdResult<DividendsResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let holder = pending_dividends(deps.storage, &addr)?;
+    Ok(DividendsResponse {
+        claimable: holder.pending,
+        index: holder.index,
+    })
+}
+
+pub fn query_bonus_weights(deps: Deps, round: u32) -> StdResult<BonusWeightsResponse> {
+    let weights = BONUS_WEIGHTS
+        .prefix(round.to_string())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(addr, weight)| Investor {
+                addr: addr.to_string(),
+                amount: weight,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(BonusWeightsResponse { round, weights })
+}
+
+pub fn query_fees(deps: Deps) -> StdResult<FeesResponse> {
+    let fees = FEES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin::new(amount.u128(), denom)))
+        .collect::<StdResult<_>>()?;
+    Ok(FeesResponse { fees })
+}
+
+fn list_members(
+    deps: Deps,
+    list: Map<&Addr, Empty>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    list.keys(
+        deps.storage,
+        start.as_ref().map(Bound::exclusive),
+        None,
+        Order::Ascending,
+    )
+    .take(limit)
+    .map(|addr| addr.map(String::from))
+    .collect()
+}
+
+pub fn query_allowlist(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllowlistResponse> {
+    let config = ALLOWLIST_CONFIG.may_load(deps.storage)?;
+    Ok(AllowlistResponse {
+        enabled: config.as_ref().is_some_and(|c| c.enabled),
+        merkle_root: config.and_then(|c| c.merkle_root),
+        members: list_members(deps, ALLOWLIST, start_after, limit)?,
+    })
+}
+
+pub fn query_blocklist(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<BlocklistResponse> {
+    Ok(BlocklistResponse {
+        members: list_members(deps, BLOCKLIST, start_after, limit)?,
+    })
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct CurrentRoundResponse {
     pub round: u32,
@@ -125,6 +701,9 @@ pub struct CurrentRoundResponse {
 pub struct CurrentInvestmentResponse {
     pub round: u32,
     pub total_amount: Uint128,
+    pub deadline: Option<Timestamp>,
+    pub sponsored_amount: Uint128,
+    pub swept_amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -149,3 +728,237 @@ pub struct TotalSupplyResponse {
 pub struct TokenBalanceResponse {
     pub balance: Uint128,
 }
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ClaimableResponse {
+    pub amount: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FeesResponse {
+    pub fees: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DenomsResponse {
+    pub denoms: Vec<AcceptedDenom>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PoolsResponse {
+    pub round: u32,
+    pub pools: Vec<Pool>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TicketsResponse {
+    pub round: u32,
+    pub owner: String,
+    pub tickets: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LottoTicketResponse {
+    pub round: u32,
+    pub ticket: u64,
+    pub owner: String,
+    pub numbers: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LottoResultResponse {
+    pub round: u32,
+    pub seed: Binary,
+    pub numbers: Vec<u8>,
+    pub tiers: Vec<TierResult>,
+    pub rollover: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakingInfoResponse {
+    pub validators: Vec<WeightedValidator>,
+    pub unbonding_period: u64,
+    pub total_staked: Uint128,
+    pub unbond_batch: UnbondBatch,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PrincipalUnbonding {
+    pub amount: Uint128,
+    pub batch: u64,
+    pub release_at: Option<Timestamp>, // none until the batch is undelegated
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PrincipalResponse {
+    pub principal: Uint128,
+    pub unbondings: Vec<PrincipalUnbonding>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ValidatorDelegation {
+    pub validator: String,
+    pub weight: u64,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DelegationsResponse {
+    pub delegations: Vec<ValidatorDelegation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct UnbondingQueueResponse {
+    pub unbondings: Vec<ValidatorUnbonding>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PauseInfoResponse {
+    pub pause: Option<PauseInfo>, // none while not paused
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RoleMembers {
+    pub role: Role,
+    pub members: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RolesResponse {
+    pub owner: String, // holds every role
+    pub roles: Vec<RoleMembers>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub code_id: u64,
+    pub msg: InstantiateMsg,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TemplatesResponse {
+    pub templates: Vec<TemplateInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LotteryInfo {
+    pub address: String,
+    pub template: String,
+    pub label: String,
+    pub created_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LotteriesResponse {
+    pub lotteries: Vec<LotteryInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LottoChainResponse {
+    pub tip: Option<Binary>, // last revealed link of the hash chain
+    pub remaining: u64,      // rounds the chain still seeds
+    pub sales_closed: bool,  // the seed of the current round is revealed
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ScratchInfoResponse {
+    pub price: Uint128,
+    pub prizes: Vec<ScratchPrize>,
+    pub bankroll: Uint128,
+    pub tip: Option<Binary>, // last revealed link of the hash chain
+    pub remaining: u64,      // plays the chain still takes
+    pub plays: u64,
+    pub pending: u64, // plays waiting for the reveal of their link
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AllowlistResponse {
+    pub enabled: bool,
+    pub merkle_root: Option<Binary>,
+    pub members: Vec<String>, // written members, not the ones under the merkle root
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BlocklistResponse {
+    pub members: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PlayerLimitsResponse {
+    pub excluded_until: Option<Timestamp>, // none once the exclusion is over
+    pub deposit_limit: DepositLimit,
+    pub pending: Option<PendingDepositLimit>,
+    pub deposited_day: Uint128,  // over the last 24 hours
+    pub deposited_week: Uint128, // over the last 7 days
+    pub remaining_day: Option<Uint128>,
+    pub remaining_week: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReferralStatsResponse {
+    pub referrer: Option<String>, // who referred `addr`
+    pub referees: u64,
+    pub earned: Uint128,
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SponsorInfo {
+    pub sponsor: String,
+    pub amount: Uint128,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SponsorsResponse {
+    pub round: u32,
+    pub total_amount: Uint128,
+    pub sponsors: Vec<SponsorInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SubscriptionInfo {
+    pub addr: String,
+    pub amount_per_round: Uint128,
+    pub remaining_rounds: u32,
+    pub start_round: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SubscriptionsResponse {
+    pub subscriptions: Vec<SubscriptionInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AutoReinvestResponse {
+    pub enabled: bool,
+    pub percent: u8, // of each prize, reinvested into the next round
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VestingInfo {
+    pub round: u32,
+    pub total: Uint128,
+    pub claimed: Uint128,
+    pub claimable: Uint128, // unlocked and not claimed yet
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VestingResponse {
+    pub schedules: Vec<VestingInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DividendsResponse {
+    pub claimable: Uint128,
+    pub index: Decimal, // `use_denom` paid per LTT so far
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BonusWeightsResponse {
+    pub round: u32,
+    pub weights: Vec<Investor>, // weight bought with LTT, not in the prize
+}
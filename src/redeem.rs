@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{
+    Addr, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult, Storage, Uint128,
+};
+
+use crate::access::ensure_allowed;
+use crate::contract::burn_token;
+use crate::error::ContractError;
+use crate::event::{Event, RedeemedForTicketEvent};
+use crate::nft::mint_ticket_msg;
+use crate::responsible::record_deposit;
+use crate::state::{
+    Investor, BONUS_WEIGHTS, CURRENT, INVESTMENTS, LOTTO_CONFIG, OWNED_TICKETS, REDEEM_RATE,
+    STAKING_CONFIG, TICKETS, TICKET_CONFIG, TICKET_NFT,
+};
+use crate::tickets::MAX_TICKETS_PER_BUY;
+
+/// Burn LTT of the sender for bonus draw weight in the current round, one unit per
+/// `REDEEM_RATE` LTT. In ticket mode a unit is a free ticket. Bonus weight adds nothing
+/// to the prize and is lost when the round is cancelled.
+pub fn handle_redeem_for_ticket(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let rate = REDEEM_RATE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::RedeemDisabled {})?;
+    // lotto tickets need numbers and no-loss entries are principals
+    if LOTTO_CONFIG.may_load(deps.storage)?.is_some()
+        || STAKING_CONFIG.may_load(deps.storage)?.is_some()
+    {
+        return Err(ContractError::InvalidParams {});
+    }
+    let weight = amount / rate;
+    if weight.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    // a unit is a ticket in ticket mode, bounded like a purchase
+    let tickets = TICKET_CONFIG.may_load(deps.storage)?.is_some();
+    if tickets && weight > Uint128::from(MAX_TICKETS_PER_BUY) {
+        return Err(ContractError::InvalidParams {});
+    }
+    ensure_allowed(deps.storage, &info.sender)?;
+    // nothing is deposited, only a self-exclusion applies
+    record_deposit(deps.storage, &env, &info.sender, Uint128::zero())?;
+
+    // only whole units are burned
+    let burned = weight * rate;
+    burn_token(deps.storage, &env, &info.sender, burned)?;
+
+    let round = CURRENT.load(deps.storage)?.round;
+    let mut rsp = Response::new();
+    if tickets {
+        // the ticket draw picks among the tickets, the weight is not kept
+        let count = weight.u128() as u64;
+        let mut investment = INVESTMENTS.load(deps.storage, round.to_string())?;
+        let first_ticket = investment.ticket_count + 1;
+        for id in first_ticket..first_ticket + count {
+            TICKETS.save(deps.storage, (round.to_string(), id), &info.sender)?;
+            OWNED_TICKETS.save(
+                deps.storage,
+                (round.to_string(), &info.sender, id),
+                &Empty {},
+            )?;
+        }
+        investment.ticket_count += count;
+        INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
+        if let Some(collection) = TICKET_NFT.may_load(deps.storage)? {
+            for id in first_ticket..first_ticket + count {
+                rsp = rsp.add_message(mint_ticket_msg(&collection, &info.sender, round, id)?);
+            }
+        }
+    } else {
+        BONUS_WEIGHTS.update(
+            deps.storage,
+            (round.to_string(), &info.sender),
+            |w| -> StdResult<_> { Ok(w.unwrap_or_default().checked_add(weight)?) },
+        )?;
+    }
+
+    RedeemedForTicketEvent {
+        round,
+        who: info.sender.as_str(),
+        burned,
+        weight,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// `investors` of `round` with their bonus weight added, and the players entered by bonus
+/// weight only. For the draw, the prize stays what the investors paid.
+pub fn with_bonus_weights(
+    storage: &dyn Storage,
+    round: u32,
+    investors: &[Investor],
+) -> StdResult<Vec<Investor>> {
+    let mut entrants = investors
+        .iter()
+        .map(|i| (Addr::unchecked(&i.addr), i.amount))
+        .collect::<BTreeMap<_, _>>();
+    for item in BONUS_WEIGHTS
+        .prefix(round.to_string())
+        .range(storage, None, None, Order::Ascending)
+    {
+        let (addr, weight) = item?;
+        *entrants.entry(addr).or_default() += weight;
+    }
+    Ok(entrants
+        .into_iter()
+        .map(|(addr, amount)| Investor {
+            addr: addr.to_string(),
+            amount,
+        })
+        .collect())
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{
+    Addr, Api, BankMsg, Coin, DepsMut, Empty, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::event::{ReferralRewardsClaimedEvent, Event};
+use crate::state::{
+    Investor, CONTRACT_INFO, INVESTED, REFERRAL_SHARE, REFERRAL_STATS, REFERRERS,
+};
+
+/// Bind `referrer` to `player` on their first entry, of any kind. Called on every entry
+/// path, to mark the player as entered. A referrer sent with a later entry is ignored.
+pub fn bind_referrer(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    player: &Addr,
+    referrer: Option<String>,
+) -> Result<(), ContractError> {
+    let referrer = referrer.map(|r| api.addr_validate(&r)).transpose()?;
+    if referrer.as_ref() == Some(player) {
+        return Err(ContractError::InvalidParams {});
+    }
+    if INVESTED.has(storage, player) {
+        return Ok(());
+    }
+    INVESTED.save(storage, player, &Empty {})?;
+    let referrer = match referrer {
+        Some(referrer) if !REFERRERS.has(storage, player) => referrer,
+        _ => return Ok(()),
+    };
+    REFERRERS.save(storage, player, &referrer)?;
+    REFERRAL_STATS.update(storage, &referrer, |s| -> StdResult<_> {
+        let mut stats = s.unwrap_or_default();
+        stats.referees += 1;
+        Ok(stats)
+    })?;
+    Ok(())
+}
+
+/// Credit referrers with their share of the owner cut `fee` of a round, in proportion to
+/// what their referees invested in it. Returns the amount taken from the owner cut.
+///
+/// In no-loss mode the investors of a round are all the principals still staked, whose
+/// rewards make up the prize. A referrer is then paid every round for as long as the
+/// referees stay deposited, as the owner is.
+pub fn credit_referrals(
+    storage: &mut dyn Storage,
+    investors: &[Investor],
+    fee: Uint128,
+) -> Result<Uint128, ContractError> {
+    let share = match REFERRAL_SHARE.may_load(storage)? {
+        Some(share) if !fee.is_zero() => share,
+        _ => return Ok(Uint128::zero()),
+    };
+    let pool = fee.multiply_ratio(share as u128, 100u128);
+    let total = investors
+        .iter()
+        .try_fold(Uint128::zero(), |t, i| t.checked_add(i.amount))?;
+
+    let mut referred = BTreeMap::<Addr, Uint128>::new();
+    for investor in investors {
+        let player = Addr::unchecked(&investor.addr);
+        if let Some(referrer) = REFERRERS.may_load(storage, &player)? {
+            *referred.entry(referrer).or_default() += investor.amount;
+        }
+    }
+
+    let mut credited = Uint128::zero();
+    for (referrer, amount) in referred {
+        let reward = pool.multiply_ratio(amount, total);
+        if reward.is_zero() {
+            continue;
+        }
+        REFERRAL_STATS.update(storage, &referrer, |s| -> StdResult<_> {
+            let mut stats = s.unwrap_or_default();
+            stats.earned = stats.earned.checked_add(reward)?;
+            stats.claimable = stats.claimable.checked_add(reward)?;
+            Ok(stats)
+        })?;
+        credited += reward;
+    }
+    Ok(credited)
+}
+
+pub fn handle_claim_referral_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut stats = REFERRAL_STATS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if stats.claimable.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    let amount = stats.claimable;
+    stats.claimable = Uint128::zero();
+    REFERRAL_STATS.save(deps.storage, &info.sender, &stats)?;
+
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let mut rsp = Response::new().add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin::new(amount.u128(), &contract.use_denom)],
+    });
+    ReferralRewardsClaimedEvent {
+        who: info.sender.as_str(),
+        amount,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
@@ -0,0 +1,83 @@
+use cosmwasm_std::{Addr, DepsMut, Env, MessageInfo, Response, Uint128};
+
+use crate::contract::{enter_on_behalf, is_invest_mode};
+use crate::error::ContractError;
+use crate::event::{AutoReinvestSetEvent, Event};
+use crate::pause::ensure_not_paused;
+use crate::state::{ContractInfo, Investment, PausableOperation, Payout, AUTO_REINVEST};
+
+/// Opt in or out of reinvesting `percent` of future prizes into the next round.
+pub fn handle_set_auto_reinvest(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+    percent: u8,
+) -> Result<Response, ContractError> {
+    if enabled {
+        // entries are plain investments, the other modes are not supported
+        if !is_invest_mode(deps.storage)? || percent == 0 || percent > 100 {
+            return Err(ContractError::InvalidParams {});
+        }
+        AUTO_REINVEST.save(deps.storage, &info.sender, &percent)?;
+    } else {
+        AUTO_REINVEST.remove(deps.storage, &info.sender);
+    }
+
+    let mut rsp = Response::new();
+    AutoReinvestSetEvent {
+        who: info.sender.as_str(),
+        enabled,
+        percent,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// Enter the winners who opted in with their share of `payouts` in `investment`, the
+/// round just opened, and take it off what is sent to them. A winner the round does not
+/// accept is paid in full. Returns the amount reinvested.
+pub fn reinvest_winnings(
+    mut deps: DepsMut,
+    env: &Env,
+    contract: &ContractInfo,
+    investment: &mut Investment,
+    payouts: &mut [Payout],
+) -> Result<Uint128, ContractError> {
+    let mut reinvested = Uint128::zero();
+    if !is_invest_mode(deps.storage)?
+        || ensure_not_paused(deps.storage, PausableOperation::Invest).is_err()
+    {
+        return Ok(reinvested);
+    }
+
+    for payout in payouts.iter_mut() {
+        if payout.amount.denom != contract.use_denom {
+            continue;
+        }
+        let winner = Addr::unchecked(&payout.addr);
+        let percent = match AUTO_REINVEST.may_load(deps.storage, &winner)? {
+            Some(percent) => percent,
+            None => continue,
+        };
+        let amount = payout
+            .amount
+            .amount
+            .multiply_ratio(percent as u128, 100u128);
+        if amount.is_zero()
+            || !enter_on_behalf(
+                deps.branch(),
+                env,
+                contract,
+                investment,
+                &winner,
+                amount,
+                false,
+            )?
+        {
+            continue;
+        }
+        payout.amount.amount = payout.amount.amount.checked_sub(amount)?;
+        reinvested = reinvested.checked_add(amount)?;
+    }
+    Ok(reinvested)
+}
@@ -0,0 +1,204 @@
+use cosmwasm_std::{
+    Addr, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage, Timestamp, Uint128,
+};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::event::{DepositLimitSetEvent, Event, SelfExclusionSetEvent};
+use crate::state::{DepositLimit, PendingDepositLimit, PlayerLimits, DEPOSITS, PLAYER_LIMITS};
+
+// a loosened limit, or a shortened self-exclusion, only applies after this delay
+pub const LOOSENING_DELAY: u64 = 7 * 24 * 60 * 60;
+// deposits are summed in hourly buckets, a window covers its last buckets
+const BUCKET: u64 = 60 * 60;
+pub const DAY: u64 = 24 * 60 * 60;
+pub const WEEK: u64 = 7 * DAY;
+
+/// The stricter of two optional limits, `None` being no limit.
+fn stricter(a: Option<Uint128>, b: Option<Uint128>) -> Option<Uint128> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn is_looser(new: Option<Uint128>, current: Option<Uint128>) -> bool {
+    match (new, current) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(new), Some(current)) => new > current,
+    }
+}
+
+/// Limits of `addr` as of `now`, with a pending loosening applied once it is due.
+pub fn load_player_limits(
+    storage: &dyn Storage,
+    addr: &Addr,
+    now: Timestamp,
+) -> StdResult<Option<PlayerLimits>> {
+    Ok(PLAYER_LIMITS.may_load(storage, addr)?.map(|mut limits| {
+        if let Some(pending) = limits.pending.take() {
+            if now >= pending.effective_at {
+                limits.deposit_limit = pending.limit;
+            } else {
+                limits.pending = Some(pending);
+            }
+        }
+        limits
+    }))
+}
+
+/// Deposits of `addr` in the `window` seconds before `now`.
+pub fn deposited_since(
+    storage: &dyn Storage,
+    addr: &Addr,
+    now: Timestamp,
+    window: u64,
+) -> StdResult<Uint128> {
+    let start = (now.seconds().saturating_sub(window)) / BUCKET + 1;
+    DEPOSITS
+        .prefix(addr)
+        .range(
+            storage,
+            Some(Bound::inclusive(start)),
+            None,
+            Order::Ascending,
+        )
+        .try_fold(Uint128::zero(), |total, item| Ok(total + item?.1))
+}
+
+/// Check a player may put `amount` of `use_denom` into the game, and count it against
+/// their deposit limits. A self-excluded player cannot play at all. Deposits are recorded
+/// without limits too, so that limits set later count them.
+pub fn record_deposit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    addr: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let now = env.block.time;
+    let limits = load_player_limits(storage, addr, now)?;
+    if let Some(until) = limits
+        .as_ref()
+        .and_then(|l| l.excluded_until)
+        .filter(|until| now < *until)
+    {
+        return Err(ContractError::SelfExcluded { until });
+    }
+    if amount.is_zero() {
+        return Ok(());
+    }
+
+    if let Some(limits) = &limits {
+        let windows = [
+            ("day", DAY, limits.deposit_limit.per_day),
+            ("week", WEEK, limits.deposit_limit.per_week),
+        ];
+        for (name, window, limit) in windows.iter() {
+            if let Some(limit) = limit {
+                let deposited = deposited_since(storage, addr, now, *window)?;
+                if deposited + amount > *limit {
+                    return Err(ContractError::DepositLimitExceeded {
+                        window: name.to_string(),
+                        remaining: limit.saturating_sub(deposited),
+                    });
+                }
+            }
+        }
+        PLAYER_LIMITS.save(storage, addr, limits)?;
+    }
+
+    // buckets older than the longest window are not needed anymore
+    let expired = DEPOSITS
+        .prefix(addr)
+        .keys(
+            storage,
+            None,
+            Some(Bound::inclusive(
+                now.seconds().saturating_sub(WEEK) / BUCKET,
+            )),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+    for bucket in expired {
+        DEPOSITS.remove(storage, (addr, bucket));
+    }
+    DEPOSITS.update(
+        storage,
+        (addr, now.seconds() / BUCKET),
+        |d| -> StdResult<_> { Ok(d.unwrap_or_default().checked_add(amount)?) },
+    )?;
+    Ok(())
+}
+
+/// Exclude the sender from playing until `until`. Extending the exclusion is immediate, an
+/// earlier end is only accepted `LOOSENING_DELAY` from now.
+pub fn handle_set_self_exclusion(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    until: Timestamp,
+) -> Result<Response, ContractError> {
+    let now = env.block.time;
+    if until <= now {
+        return Err(ContractError::InvalidParams {});
+    }
+    let mut limits = load_player_limits(deps.storage, &info.sender, now)?.unwrap_or_default();
+    let until = match limits.excluded_until.filter(|current| now < *current) {
+        Some(current) if until < current => {
+            until.max(now.plus_seconds(LOOSENING_DELAY)).min(current)
+        }
+        _ => until,
+    };
+    limits.excluded_until = Some(until);
+    PLAYER_LIMITS.save(deps.storage, &info.sender, &limits)?;
+
+    let mut rsp = Response::new();
+    SelfExclusionSetEvent {
+        who: info.sender.as_str(),
+        until,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// Set the deposit limits of the sender. The stricter part of the new limits applies at
+/// once, the new limits as a whole only after `LOOSENING_DELAY` when they are looser.
+pub fn handle_set_deposit_limit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    per_day: Option<Uint128>,
+    per_week: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let now = env.block.time;
+    if per_day.is_some_and(|l| l.is_zero()) || per_week.is_some_and(|l| l.is_zero()) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let mut limits = load_player_limits(deps.storage, &info.sender, now)?.unwrap_or_default();
+    let current = limits.deposit_limit.clone();
+    let requested = DepositLimit { per_day, per_week };
+
+    limits.deposit_limit = DepositLimit {
+        per_day: stricter(current.per_day, per_day),
+        per_week: stricter(current.per_week, per_week),
+    };
+    limits.pending = if is_looser(per_day, current.per_day) || is_looser(per_week, current.per_week)
+    {
+        Some(PendingDepositLimit {
+            limit: requested,
+            effective_at: now.plus_seconds(LOOSENING_DELAY),
+        })
+    } else {
+        None
+    };
+    PLAYER_LIMITS.save(deps.storage, &info.sender, &limits)?;
+
+    let mut rsp = Response::new();
+    DepositLimitSetEvent {
+        who: info.sender.as_str(),
+        effective_at: limits.pending.map(|p| p.effective_at),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
@@ -0,0 +1,62 @@
+use cosmwasm_std::{Addr, DepsMut, Empty, MessageInfo, Response, StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::event::{Event, RoleUpdatedEvent};
+use crate::state::{Role, CONTRACT_INFO, ROLES};
+
+pub fn has_role(storage: &dyn Storage, addr: &Addr, role: Role) -> StdResult<bool> {
+    if CONTRACT_INFO.load(storage)?.owner == *addr {
+        return Ok(true);
+    }
+    Ok(ROLES.has(storage, (role.as_str(), addr)))
+}
+
+pub fn ensure_role(storage: &dyn Storage, addr: &Addr, role: Role) -> Result<(), ContractError> {
+    if !has_role(storage, addr, role)? {
+        return Err(ContractError::MissingRole { role });
+    }
+    Ok(())
+}
+
+pub fn handle_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    addr: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let member = deps.api.addr_validate(&addr)?;
+    ROLES.save(deps.storage, (role.as_str(), &member), &Empty {})?;
+
+    let mut rsp = Response::new();
+    RoleUpdatedEvent {
+        action: "RoleGranted",
+        role,
+        addr: member.as_str(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+pub fn handle_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    addr: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let member = deps.api.addr_validate(&addr)?;
+    if !ROLES.has(deps.storage, (role.as_str(), &member)) {
+        return Err(ContractError::InvalidParams {});
+    }
+    ROLES.remove(deps.storage, (role.as_str(), &member));
+
+    let mut rsp = Response::new();
+    RoleUpdatedEvent {
+        action: "RoleRevoked",
+        role,
+        addr: member.as_str(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
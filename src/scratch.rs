@@ -0,0 +1,343 @@
+use cosmwasm_std::{BankMsg, Binary, Coin, DepsMut, Env, MessageInfo, Response, StdResult, Uint128};
+use sha2::{Digest, Sha256};
+
+use crate::access::ensure_allowed;
+use crate::contract::must_pay;
+use crate::error::ContractError;
+use crate::event::{
+    BankrollFundedEvent, BankrollWithdrawnEvent, Event, ScratchChainCommittedEvent,
+    ScratchPlayedEvent, ScratchRefundedEvent, ScratchedEvent,
+};
+use crate::responsible::record_deposit;
+use crate::roles::ensure_role;
+use crate::state::{
+    Role, ScratchChain, ScratchConfig, ScratchPlay, CONTRACT_INFO, SCRATCH_BANKROLL, SCRATCH_CHAIN,
+    SCRATCH_CONFIG, SCRATCH_PLAYS,
+};
+
+// outcomes are rolled in 0..ODDS_SCALE, a prize of 1 in `odds` covers ODDS_SCALE / odds of it
+const ODDS_SCALE: u64 = 1_000_000;
+// bounds the plays settled by a single reveal
+const MAX_PREIMAGES_PER_REVEAL: usize = 100;
+// a play left unrevealed this long may be refunded by anyone
+pub const SCRATCH_REVEAL_TIMEOUT: u64 = 24 * 60 * 60;
+
+pub fn validate_scratch_config(config: &ScratchConfig) -> Result<(), ContractError> {
+    if config.price.is_zero() || config.prizes.is_empty() {
+        return Err(ContractError::InvalidParams {});
+    }
+    let mut covered = 0u64;
+    for prize in &config.prizes {
+        if prize.odds == 0 || prize.odds > ODDS_SCALE || prize.multiplier == 0 {
+            return Err(ContractError::InvalidParams {});
+        }
+        covered += ODDS_SCALE / prize.odds;
+    }
+    if covered > ODDS_SCALE {
+        return Err(ContractError::InvalidParams {});
+    }
+    Ok(())
+}
+
+fn sha256(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+/// Multiplier won by the `play`-th card of `who`, 0 for a losing card. The preimage is
+/// fixed by the committed chain and revealed after the play, so neither side can pick the
+/// outcome.
+pub fn scratch_multiplier(config: &ScratchConfig, preimage: &[u8], who: &str, play: u64) -> u64 {
+    let hash = Sha256::new()
+        .chain_update(preimage)
+        .chain_update(who.as_bytes())
+        .chain_update(play.to_be_bytes())
+        .finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    let roll = u64::from_be_bytes(bytes) % ODDS_SCALE;
+
+    let mut threshold = 0u64;
+    for prize in &config.prizes {
+        threshold += ODDS_SCALE / prize.odds;
+        if roll < threshold {
+            return prize.multiplier;
+        }
+    }
+    0
+}
+
+// the bankroll held back for a play, its top prize
+fn top_prize(config: &ScratchConfig) -> StdResult<Uint128> {
+    let max_multiplier = config
+        .prizes
+        .iter()
+        .map(|p| p.multiplier)
+        .max()
+        .unwrap_or_default();
+    Ok(config.price.checked_mul(Uint128::from(max_multiplier))?)
+}
+
+/// Buy a scratch card. The play takes the next link of the chain, which the owner reveals
+/// later to settle it, so the outcome is not known to the player when paying.
+pub fn handle_scratch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = SCRATCH_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::ScratchDisabled {})?;
+    ensure_allowed(deps.storage, &info.sender)?;
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let paid = must_pay(&info, &contract.use_denom)?;
+    if paid != config.price {
+        return Err(ContractError::InvalidPayment {
+            expected: config.price,
+            received: paid,
+        });
+    }
+    record_deposit(deps.storage, &env, &info.sender, paid)?;
+
+    let mut chain = SCRATCH_CHAIN
+        .may_load(deps.storage)?
+        .filter(|c| c.remaining > 0)
+        .ok_or(ContractError::ScratchChainExhausted {})?;
+
+    // the bankroll must cover the top prize of every play waiting, this one included
+    let bankroll = SCRATCH_BANKROLL.load(deps.storage)?.checked_add(paid)?;
+    let needed = top_prize(&config)?.checked_mul(Uint128::from(chain.plays - chain.settled + 1))?;
+    if bankroll < needed {
+        return Err(ContractError::InsufficientBankroll {
+            needed,
+            available: bankroll,
+        });
+    }
+    SCRATCH_BANKROLL.save(deps.storage, &bankroll)?;
+
+    chain.plays += 1;
+    chain.remaining -= 1;
+    SCRATCH_CHAIN.save(deps.storage, &chain)?;
+    SCRATCH_PLAYS.save(
+        deps.storage,
+        chain.plays,
+        &ScratchPlay {
+            who: info.sender.clone(),
+            played_at: env.block.time,
+        },
+    )?;
+
+    let mut rsp = Response::new();
+    ScratchPlayedEvent {
+        who: info.sender.as_str(),
+        play: chain.plays,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Commit to a new hash chain of `length` links by its last one, `sha256^length(secret)`.
+/// The plays of the previous chain must all be settled, so that no outcome changes.
+pub fn handle_commit_scratch_chain(
+    deps: DepsMut,
+    info: MessageInfo,
+    anchor: Binary,
+    length: u64,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if SCRATCH_CONFIG.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::ScratchDisabled {});
+    }
+    if anchor.len() != 32 || length == 0 {
+        return Err(ContractError::InvalidParams {});
+    }
+
+    let (plays, settled) = SCRATCH_CHAIN
+        .may_load(deps.storage)?
+        .map(|c| (c.plays, c.settled))
+        .unwrap_or_default();
+    if settled < plays {
+        return Err(ContractError::ScratchPlaysPending {});
+    }
+    SCRATCH_CHAIN.save(
+        deps.storage,
+        &ScratchChain {
+            tip: anchor.clone(),
+            remaining: length,
+            plays,
+            settled,
+        },
+    )?;
+
+    let mut rsp = Response::new();
+    ScratchChainCommittedEvent {
+        anchor: &anchor.to_base64(),
+        length,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Reveal the next links of the chain, each settling the play waiting for it in play order.
+/// A play cannot be skipped, the links being fixed by the chain.
+pub fn handle_reveal_scratch(
+    deps: DepsMut,
+    info: MessageInfo,
+    preimages: Vec<Binary>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let config = SCRATCH_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::ScratchDisabled {})?;
+    let mut chain = SCRATCH_CHAIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::ScratchChainExhausted {})?;
+    if preimages.is_empty()
+        || preimages.len() > MAX_PREIMAGES_PER_REVEAL
+        || preimages.len() as u64 > chain.plays - chain.settled
+    {
+        return Err(ContractError::InvalidParams {});
+    }
+
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let mut bankroll = SCRATCH_BANKROLL.load(deps.storage)?;
+    let mut rsp = Response::new();
+    for preimage in preimages {
+        if sha256(&preimage) != chain.tip.as_slice() {
+            return Err(ContractError::InvalidPreimage {});
+        }
+        chain.settled += 1;
+        let play = chain.settled;
+        let who = SCRATCH_PLAYS.load(deps.storage, play)?.who;
+        SCRATCH_PLAYS.remove(deps.storage, play);
+
+        let multiplier = scratch_multiplier(&config, &preimage, who.as_str(), play);
+        let prize = config.price.checked_mul(Uint128::from(multiplier))?;
+        bankroll = bankroll.checked_sub(prize)?;
+        if !prize.is_zero() {
+            rsp = rsp.add_message(BankMsg::Send {
+                to_address: who.to_string(),
+                amount: vec![Coin::new(prize.u128(), &contract.use_denom)],
+            });
+        }
+        ScratchedEvent {
+            who: who.as_str(),
+            play,
+            preimage: &preimage.to_base64(),
+            multiplier,
+            prize,
+        }
+        .add_attributes(&mut rsp);
+        chain.tip = preimage;
+    }
+    SCRATCH_CHAIN.save(deps.storage, &chain)?;
+    SCRATCH_BANKROLL.save(deps.storage, &bankroll)?;
+
+    Ok(rsp)
+}
+
+/// Give back the stakes of the plays waiting, once the oldest one waited for its reveal
+/// longer than `SCRATCH_REVEAL_TIMEOUT`, so that the owner cannot hold them forever. Anyone
+/// may call it. The chain is given up as its links no longer match the plays, more plays
+/// need a new chain.
+pub fn handle_refund_scratch(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = SCRATCH_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::ScratchDisabled {})?;
+    let mut chain = SCRATCH_CHAIN
+        .may_load(deps.storage)?
+        .filter(|c| c.settled < c.plays)
+        .ok_or(ContractError::ScratchRevealNotDue {})?;
+    // a given up chain is refunded to the end, whenever its plays were made
+    let oldest = SCRATCH_PLAYS.load(deps.storage, chain.settled + 1)?;
+    if !chain.tip.is_empty()
+        && env.block.time < oldest.played_at.plus_seconds(SCRATCH_REVEAL_TIMEOUT)
+    {
+        return Err(ContractError::ScratchRevealNotDue {});
+    }
+
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let mut bankroll = SCRATCH_BANKROLL.load(deps.storage)?;
+    let mut rsp = Response::new();
+    let last = chain
+        .plays
+        .min(chain.settled + MAX_PREIMAGES_PER_REVEAL as u64);
+    for play in chain.settled + 1..=last {
+        let who = SCRATCH_PLAYS.load(deps.storage, play)?.who;
+        SCRATCH_PLAYS.remove(deps.storage, play);
+        bankroll = bankroll.checked_sub(config.price)?;
+        rsp = rsp.add_message(BankMsg::Send {
+            to_address: who.to_string(),
+            amount: vec![Coin::new(config.price.u128(), &contract.use_denom)],
+        });
+        ScratchRefundedEvent {
+            who: who.as_str(),
+            play,
+            amount: config.price,
+        }
+        .add_attributes(&mut rsp);
+    }
+    // no link hashes to an empty tip
+    chain.tip = Binary::default();
+    chain.remaining = 0;
+    chain.settled = last;
+    SCRATCH_CHAIN.save(deps.storage, &chain)?;
+    SCRATCH_BANKROLL.save(deps.storage, &bankroll)?;
+
+    Ok(rsp)
+}
+
+pub fn handle_fund_bankroll(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    ensure_role(deps.storage, &info.sender, Role::Treasurer)?;
+    if SCRATCH_CONFIG.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::ScratchDisabled {});
+    }
+    let amount = must_pay(&info, &contract.use_denom)?;
+    let bankroll = SCRATCH_BANKROLL.update(deps.storage, |b| -> StdResult<_> {
+        Ok(b.checked_add(amount)?)
+    })?;
+
+    let mut rsp = Response::new();
+    BankrollFundedEvent { amount, bankroll }.add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+pub fn handle_withdraw_bankroll(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    ensure_role(deps.storage, &info.sender, Role::Treasurer)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let config = SCRATCH_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::ScratchDisabled {})?;
+    // the top prizes of the plays waiting stay in the bankroll
+    let pending = SCRATCH_CHAIN
+        .may_load(deps.storage)?
+        .map(|c| c.plays - c.settled)
+        .unwrap_or_default();
+    let reserved = top_prize(&config)?.checked_mul(Uint128::from(pending))?;
+    let bankroll = SCRATCH_BANKROLL.load(deps.storage)?;
+    let available = bankroll.saturating_sub(reserved);
+    if amount > available {
+        return Err(ContractError::InsufficientBankroll {
+            needed: amount,
+            available,
+        });
+    }
+    let bankroll = bankroll - amount;
+    SCRATCH_BANKROLL.save(deps.storage, &bankroll)?;
+
+    let mut rsp = Response::new().add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin::new(amount.u128(), &contract.use_denom)],
+    });
+    BankrollWithdrawnEvent { amount, bankroll }.add_attributes(&mut rsp);
+    Ok(rsp)
+}
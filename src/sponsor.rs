@@ -0,0 +1,54 @@
+use cosmwasm_std::{DepsMut, MessageInfo, Response, StdResult};
+
+use crate::contract::must_pay;
+use crate::error::ContractError;
+use crate::event::{Event, SponsoredEvent};
+use crate::state::{CONTRACT_INFO, CURRENT, INVESTMENTS, SPONSORS};
+
+// bounds the message stored with a sponsorship
+const MAX_MESSAGE_LEN: usize = 256;
+
+/// Add funds to the prize of the current round. Sponsors are not investors, they take no
+/// part in the draw and get no LTT.
+pub fn handle_sponsor(
+    deps: DepsMut,
+    info: MessageInfo,
+    round: u32,
+    message: Option<String>,
+) -> Result<Response, ContractError> {
+    let current = CURRENT.load(deps.storage)?.round;
+    if round != current {
+        return Err(ContractError::InvalidRound { round });
+    }
+    if message.as_ref().is_some_and(|m| m.len() > MAX_MESSAGE_LEN) {
+        return Err(ContractError::InvalidParams {});
+    }
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let amount = must_pay(&info, &contract.use_denom)?;
+
+    let mut investment = INVESTMENTS.load(deps.storage, round.to_string())?;
+    investment.sponsored_amount = investment.sponsored_amount.checked_add(amount)?;
+    INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
+    SPONSORS.update(
+        deps.storage,
+        (round.to_string(), &info.sender),
+        |s| -> StdResult<_> {
+            let mut sponsorship = s.unwrap_or_default();
+            sponsorship.amount = sponsorship.amount.checked_add(amount)?;
+            if message.is_some() {
+                sponsorship.message = message.clone();
+            }
+            Ok(sponsorship)
+        },
+    )?;
+
+    let mut rsp = Response::new();
+    SponsoredEvent {
+        round,
+        sponsor: info.sender.as_str(),
+        amount,
+        message: message.as_deref().unwrap_or_default(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
@@ -0,0 +1,663 @@
+use cosmwasm_std::{
+    BankMsg, Coin, CosmosMsg, Deps, DepsMut, DistributionMsg, Env, MessageInfo, Reply, Response,
+    StakingMsg, StdResult, Storage, SubMsg, SubMsgResult, Timestamp, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::event::{
+    BatchUndelegatedEvent, Event, PrincipalWithdrawnEvent, UnbondedEvent, UnbondingScheduledEvent,
+    ValidatorsUpdatedEvent,
+};
+use crate::roles::ensure_role;
+use crate::state::{
+    PendingUnbond, Role, StakingConfig, UnbondBatch, Unbonding, ValidatorUnbonding,
+    WeightedValidator, CONTRACT_INFO, PENDING_UNBOND, PRINCIPALS, STAKING, STAKING_CONFIG,
+    UNBONDINGS, UNBONDING_QUEUE, UNBOND_BATCH, UNBOND_RELEASES, UNDRAWN_REWARDS,
+};
+
+pub const UNBOND_REPLY_ID: u64 = 3;
+// batches of unbonds are undelegated at most once per unbonding_period / UNBOND_EPOCHS, so
+// no more than 7 undelegations of a validator are ever unbonding
+pub const UNBOND_EPOCHS: u64 = 6;
+
+/// Split `amount` by the validator weights, the remainder goes to the first one.
+pub fn split_by_weight(
+    validators: &[WeightedValidator],
+    amount: Uint128,
+) -> Vec<(String, Uint128)> {
+    let total_weight: u128 = validators.iter().map(|v| v.weight as u128).sum();
+    let mut shares = validators
+        .iter()
+        .map(|v| {
+            (
+                v.address.clone(),
+                amount.multiply_ratio(v.weight, total_weight),
+            )
+        })
+        .collect::<Vec<_>>();
+    let remainder = amount - shares.iter().map(|(_, share)| *share).sum::<Uint128>();
+    if let Some(first) = shares.first_mut() {
+        first.1 += remainder;
+    }
+    shares
+}
+
+pub fn validate_validators(validators: &[WeightedValidator]) -> Result<(), ContractError> {
+    let total_weight = validators
+        .iter()
+        .try_fold(0u64, |total, v| total.checked_add(v.weight))
+        .ok_or(ContractError::InvalidParams {})?;
+    if total_weight == 0 {
+        return Err(ContractError::InvalidParams {});
+    }
+    for (i, v) in validators.iter().enumerate() {
+        if v.address.is_empty() || validators[..i].iter().any(|o| o.address == v.address) {
+            return Err(ContractError::InvalidParams {});
+        }
+    }
+    Ok(())
+}
+
+pub fn delegate_msgs(config: &StakingConfig, amount: Uint128, denom: &str) -> Vec<SubMsg> {
+    split_by_weight(&config.validators, amount)
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(validator, amount)| {
+            SubMsg::new(StakingMsg::Delegate {
+                validator,
+                amount: Coin::new(amount.u128(), denom),
+            })
+        })
+        .collect()
+}
+
+/// Current delegations of the contract to each of `validators`.
+pub fn current_delegations(
+    deps: Deps,
+    env: &Env,
+    validators: &[String],
+) -> StdResult<Vec<(String, Uint128)>> {
+    validators
+        .iter()
+        .map(|validator| {
+            let amount = deps
+                .querier
+                .query_delegation(env.contract.address.clone(), validator)?
+                .map(|d| d.amount.amount)
+                .unwrap_or_default();
+            Ok((validator.clone(), amount))
+        })
+        .collect()
+}
+
+/// Pick the undelegations for `amount`: first the stake above each validator's weighted
+/// target, then whatever is left in validator order.
+pub fn undelegations(
+    deps: Deps,
+    env: &Env,
+    config: &StakingConfig,
+    amount: Uint128,
+) -> Result<Vec<(String, Uint128)>, ContractError> {
+    let addresses = config
+        .validators
+        .iter()
+        .map(|v| v.address.clone())
+        .collect::<Vec<_>>();
+    let mut delegated = current_delegations(deps, env, &addresses)?;
+    let total = delegated.iter().map(|(_, a)| *a).sum::<Uint128>();
+    if total < amount {
+        return Err(ContractError::CustomError {
+            val: format!("insufficient delegation: {} < {}", total, amount),
+        });
+    }
+    let targets = split_by_weight(&config.validators, total - amount);
+
+    let mut left = amount;
+    let mut takes = vec![Uint128::zero(); delegated.len()];
+    for (i, (_, target)) in targets.iter().enumerate() {
+        let take = delegated[i].1.saturating_sub(*target).min(left);
+        takes[i] += take;
+        delegated[i].1 -= take;
+        left -= take;
+    }
+    for (i, (_, available)) in delegated.iter().enumerate() {
+        let take = (*available).min(left);
+        takes[i] += take;
+        left -= take;
+    }
+
+    Ok(addresses
+        .into_iter()
+        .zip(takes)
+        .filter(|(_, take)| !take.is_zero())
+        .collect())
+}
+
+/// Redelegations moving the stake held by `current` validators to match the weights of
+/// `validators`.
+pub fn rebalance_msgs(
+    deps: Deps,
+    env: &Env,
+    current: &[String],
+    validators: &[WeightedValidator],
+    denom: &str,
+) -> StdResult<Vec<SubMsg>> {
+    let mut addresses = current.to_vec();
+    for v in validators.iter() {
+        if !addresses.contains(&v.address) {
+            addresses.push(v.address.clone());
+        }
+    }
+    let delegated = current_delegations(deps, env, &addresses)?;
+    let total = delegated.iter().map(|(_, a)| *a).sum::<Uint128>();
+    let targets = split_by_weight(validators, total);
+    let target_of = |addr: &str| {
+        targets
+            .iter()
+            .find(|(a, _)| a == addr)
+            .map(|(_, t)| *t)
+            .unwrap_or_default()
+    };
+
+    let mut over = delegated
+        .iter()
+        .filter(|(addr, amount)| *amount > target_of(addr))
+        .map(|(addr, amount)| (addr.clone(), *amount - target_of(addr)))
+        .collect::<Vec<_>>();
+    let mut under = delegated
+        .iter()
+        .filter(|(addr, amount)| *amount < target_of(addr))
+        .map(|(addr, amount)| (addr.clone(), target_of(addr) - *amount))
+        .collect::<Vec<_>>();
+
+    let mut msgs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < over.len() && j < under.len() {
+        let amount = over[i].1.min(under[j].1);
+        msgs.push(SubMsg::new(StakingMsg::Redelegate {
+            src_validator: over[i].0.clone(),
+            dst_validator: under[j].0.clone(),
+            amount: Coin::new(amount.u128(), denom),
+        }));
+        over[i].1 -= amount;
+        under[j].1 -= amount;
+        if over[i].1.is_zero() {
+            i += 1;
+        }
+        if under[j].1.is_zero() {
+            j += 1;
+        }
+    }
+    Ok(msgs)
+}
+
+/// Drop the validators which left the active set (jailed, unbonded or removed) and spread
+/// their stake over the remaining ones. Returns the redelegations and the dropped
+/// validators.
+pub fn drop_inactive_validators(
+    deps: Deps,
+    env: &Env,
+    config: &mut StakingConfig,
+    denom: &str,
+) -> Result<(Vec<SubMsg>, Vec<String>), ContractError> {
+    let current = config
+        .validators
+        .iter()
+        .map(|v| v.address.clone())
+        .collect::<Vec<_>>();
+    // a jailed or unbonded validator is still known to the chain, only the active set
+    // tells it apart
+    let active_set = deps.querier.query_all_validators()?;
+    let mut active = vec![];
+    let mut removed = vec![];
+    for v in config.validators.iter() {
+        if active_set.iter().any(|a| a.address == v.address) {
+            active.push(v.clone());
+        } else {
+            removed.push(v.address.clone());
+        }
+    }
+    if removed.is_empty() {
+        return Ok((vec![], removed));
+    }
+    if active.iter().all(|v| v.weight == 0) {
+        return Err(ContractError::NoActiveValidators {});
+    }
+
+    let msgs = rebalance_msgs(deps, env, &current, &active, denom)?;
+    config.validators = active;
+    Ok((msgs, removed))
+}
+
+/// Record undelegations in the unbonding queue, dropping the matured ones.
+fn push_unbonding_queue(
+    storage: &mut dyn Storage,
+    now: Timestamp,
+    entries: Vec<ValidatorUnbonding>,
+) -> StdResult<()> {
+    let mut queue = UNBONDING_QUEUE.may_load(storage)?.unwrap_or_default();
+    queue.retain(|u| u.matures_at > now);
+    queue.extend(entries);
+    UNBONDING_QUEUE.save(storage, &queue)
+}
+
+/// Rewards in `denom` accrued by the delegation to `validator`, None without one.
+fn accrued_rewards(
+    deps: Deps,
+    env: &Env,
+    validator: &str,
+    denom: &str,
+) -> StdResult<Option<Uint128>> {
+    let delegation = deps
+        .querier
+        .query_delegation(env.contract.address.clone(), validator)?;
+    Ok(delegation.map(|d| {
+        d.accumulated_rewards
+            .iter()
+            .filter(|c| c.denom == denom)
+            .map(|c| c.amount)
+            .sum()
+    }))
+}
+
+/// Sum up the accrued rewards of all validators and build the messages to claim them.
+/// The rewards paid out earlier by delegation changes are added to the sum.
+pub fn withdraw_reward_msgs(
+    deps: Deps,
+    env: &Env,
+    config: &StakingConfig,
+    denom: &str,
+) -> StdResult<(Uint128, Vec<SubMsg>)> {
+    let mut rewards = UNDRAWN_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+    let mut msgs = vec![];
+    for validator in config.validators.iter() {
+        if let Some(accrued) = accrued_rewards(deps, env, &validator.address, denom)? {
+            rewards += accrued;
+            msgs.push(SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
+                validator: validator.address.clone(),
+            }));
+        }
+    }
+    Ok((rewards, msgs))
+}
+
+/// The chain pays out the accrued rewards of a delegation to the contract whenever the
+/// delegation changes. Keep those of the validators `msgs` touch for the next draw, the
+/// withdrawal at close does not see them any more.
+pub fn track_withdrawn_rewards(
+    deps: DepsMut,
+    env: &Env,
+    msgs: &[SubMsg],
+    denom: &str,
+) -> StdResult<()> {
+    let mut validators: Vec<&String> = vec![];
+    for msg in msgs.iter() {
+        let touched = match &msg.msg {
+            CosmosMsg::Staking(StakingMsg::Delegate { validator, .. })
+            | CosmosMsg::Staking(StakingMsg::Undelegate { validator, .. }) => vec![validator],
+            CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator,
+                dst_validator,
+                ..
+            }) => vec![src_validator, dst_validator],
+            _ => vec![],
+        };
+        for validator in touched {
+            if !validators.contains(&validator) {
+                validators.push(validator);
+            }
+        }
+    }
+
+    let mut withdrawn = Uint128::zero();
+    for validator in validators {
+        withdrawn += accrued_rewards(deps.as_ref(), env, validator, denom)?.unwrap_or_default();
+    }
+    if withdrawn.is_zero() {
+        return Ok(());
+    }
+    let undrawn = UNDRAWN_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+    UNDRAWN_REWARDS.save(deps.storage, &undrawn.checked_add(withdrawn)?)
+}
+
+/// Queue `amount` of the sender's principal for the next batch of undelegations.
+pub fn handle_unbond(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if STAKING_CONFIG.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoLossDisabled {});
+    }
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    PRINCIPALS.update(deps.storage, &info.sender, |p| -> StdResult<_> {
+        Ok(p.unwrap_or_default().checked_sub(amount)?)
+    })?;
+    STAKING.update(deps.storage, |s| -> StdResult<_> {
+        Ok(s.checked_sub(amount)?)
+    })?;
+
+    let mut batch = UNBOND_BATCH.may_load(deps.storage)?.unwrap_or_default();
+    batch.amount = batch.amount.checked_add(amount)?;
+    UNBOND_BATCH.save(deps.storage, &batch)?;
+    UNBONDINGS.update(deps.storage, &info.sender, |u| -> StdResult<_> {
+        let mut unbondings = u.unwrap_or_default();
+        unbondings.push(Unbonding {
+            amount,
+            batch: batch.id,
+        });
+        Ok(unbondings)
+    })?;
+
+    let mut rsp = Response::new();
+    UnbondedEvent {
+        who: info.sender.as_str(),
+        amount,
+        batch: batch.id,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Undelegate the unbonds of the open batch, at most once per epoch. The chain keeps at
+/// most 7 unbonding entries per validator, and undelegating every unbond on its own would
+/// soon hit that limit.
+fn undelegate_batch(
+    mut deps: DepsMut,
+    env: &Env,
+    config: &StakingConfig,
+    denom: &str,
+) -> Result<Response, ContractError> {
+    let batch = UNBOND_BATCH.may_load(deps.storage)?.unwrap_or_default();
+    let epoch = config.unbonding_period / UNBOND_EPOCHS;
+    let waiting = batch
+        .last_undelegated_at
+        .is_some_and(|at| env.block.time < at.plus_seconds(epoch));
+    if batch.amount.is_zero() || waiting {
+        return Ok(Response::new());
+    }
+
+    // an estimate, the chain sets the completion time of each undelegation in the reply
+    let release_at = env.block.time.plus_seconds(config.unbonding_period);
+    let undelegations = undelegations(deps.as_ref(), env, config, batch.amount)?;
+    PENDING_UNBOND.save(
+        deps.storage,
+        &PendingUnbond {
+            batch: batch.id,
+            entries: undelegations.len() as u32,
+            replies_left: undelegations.len() as u32,
+        },
+    )?;
+    push_unbonding_queue(
+        deps.storage,
+        env.block.time,
+        undelegations
+            .iter()
+            .map(|(validator, amount)| ValidatorUnbonding {
+                validator: validator.clone(),
+                amount: *amount,
+                matures_at: release_at,
+            })
+            .collect(),
+    )?;
+    UNBOND_RELEASES.save(deps.storage, batch.id, &release_at)?;
+    UNBOND_BATCH.save(
+        deps.storage,
+        &UnbondBatch {
+            id: batch.id + 1,
+            amount: Uint128::zero(),
+            last_undelegated_at: Some(env.block.time),
+        },
+    )?;
+
+    let submsgs = undelegations
+        .into_iter()
+        .map(|(validator, amount)| {
+            SubMsg::reply_on_success(
+                StakingMsg::Undelegate {
+                    validator,
+                    amount: Coin::new(amount.u128(), denom),
+                },
+                UNBOND_REPLY_ID,
+            )
+        })
+        .collect::<Vec<_>>();
+    track_withdrawn_rewards(deps.branch(), env, &submsgs, denom)?;
+
+    let mut rsp = Response::new().add_submessages(submsgs);
+    BatchUndelegatedEvent {
+        batch: batch.id,
+        amount: batch.amount,
+        release_at: release_at.seconds(),
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Set the maturity of an undelegation sent by `undelegate_batch` to the completion time
+/// the chain reports in its `unbond` event. The unbondings of the batch are released with
+/// the last of its undelegations. Without the event the estimate is kept.
+pub fn handle_unbond_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let mut pending = PENDING_UNBOND.load(deps.storage)?;
+    pending.replies_left = pending.replies_left.saturating_sub(1);
+    if pending.replies_left == 0 {
+        PENDING_UNBOND.remove(deps.storage);
+    } else {
+        PENDING_UNBOND.save(deps.storage, &pending)?;
+    }
+
+    let events = match msg.result {
+        SubMsgResult::Ok(response) => response.events,
+        SubMsgResult::Err(val) => return Err(ContractError::CustomError { val }),
+    };
+    let completion = events.iter().filter(|e| e.ty == "unbond").find_map(|e| {
+        let attr = |key: &str| e.attributes.iter().find(|a| a.key == key);
+        let validator = attr("validator")?.value.clone();
+        let completes_at = parse_rfc3339(&attr("completion_time")?.value)?;
+        Some((validator, completes_at))
+    });
+    let (validator, completes_at) = match completion {
+        Some(completion) => completion,
+        None => return Ok(Response::new()),
+    };
+
+    let mut queue = UNBONDING_QUEUE.load(deps.storage)?;
+    let start = queue.len().saturating_sub(pending.entries as usize);
+    let batch = &mut queue[start..];
+    if let Some(entry) = batch.iter_mut().find(|u| u.validator == validator) {
+        entry.matures_at = completes_at;
+    }
+    let release_at = batch
+        .iter()
+        .map(|u| u.matures_at)
+        .max()
+        .unwrap_or(completes_at);
+    UNBONDING_QUEUE.save(deps.storage, &queue)?;
+    UNBOND_RELEASES.save(deps.storage, pending.batch, &release_at)?;
+
+    let mut rsp = Response::new();
+    UnbondingScheduledEvent {
+        batch: pending.batch,
+        validator: &validator,
+        release_at: release_at.seconds(),
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// Parse an RFC 3339 time as the chain writes it, e.g. `2022-09-27T14:00:00Z` or with an
+/// offset and fractional seconds.
+fn parse_rfc3339(value: &str) -> Option<Timestamp> {
+    let num = |s: Option<&str>| s?.parse::<i64>().ok();
+    let (date, time) = value.split_once('T')?;
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (num(parts.next())?, num(parts.next())?, num(parts.next())?);
+
+    let (clock, offset) = match time.strip_suffix('Z') {
+        Some(clock) => (clock, 0),
+        None => {
+            let at = time.rfind(['+', '-'])?;
+            let (clock, zone) = time.split_at(at);
+            let (hours, minutes) = zone[1..].split_once(':')?;
+            let offset = num(Some(hours))? * 3600 + num(Some(minutes))? * 60;
+            (
+                clock,
+                if zone.starts_with('-') {
+                    -offset
+                } else {
+                    offset
+                },
+            )
+        }
+    };
+    let (clock, nanos) = match clock.split_once('.') {
+        Some((clock, fraction)) => {
+            let digits = fraction.get(..9.min(fraction.len()))?;
+            let nanos = num(Some(digits))? * 10i64.pow(9 - digits.len() as u32);
+            (clock, nanos)
+        }
+        None => (clock, 0),
+    };
+    let mut parts = clock.splitn(3, ':');
+    let (hour, minute, second) = (num(parts.next())?, num(parts.next())?, num(parts.next())?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // days since 1970-01-01 of the proleptic Gregorian calendar
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    if seconds < 0 {
+        return None;
+    }
+    Some(Timestamp::from_seconds(seconds as u64).plus_nanos(nanos as u64))
+}
+
+pub fn handle_withdraw_principal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if STAKING_CONFIG.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoLossDisabled {});
+    }
+    let denom = CONTRACT_INFO.load(deps.storage)?.use_denom;
+
+    let mut released = vec![];
+    let mut pending = vec![];
+    for unbonding in UNBONDINGS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default()
+    {
+        // the chain pays out an unbonding at the end of the block it completes in
+        match UNBOND_RELEASES.may_load(deps.storage, unbonding.batch)? {
+            Some(release_at) if release_at < env.block.time => released.push(unbonding),
+            _ => pending.push(unbonding),
+        }
+    }
+    let amount: Uint128 = released.iter().map(|u| u.amount).sum();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    if pending.is_empty() {
+        UNBONDINGS.remove(deps.storage, &info.sender);
+    } else {
+        UNBONDINGS.save(deps.storage, &info.sender, &pending)?;
+    }
+
+    let mut rsp = Response::new().add_submessage(SubMsg::new(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin::new(amount.u128(), denom)],
+    }));
+    PrincipalWithdrawnEvent {
+        who: info.sender.as_str(),
+        amount,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+pub fn handle_update_validators(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validators: Vec<WeightedValidator>,
+) -> Result<Response, ContractError> {
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let mut config = STAKING_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoLossDisabled {})?;
+    validate_validators(&validators)?;
+
+    let current = config
+        .validators
+        .iter()
+        .map(|v| v.address.clone())
+        .collect::<Vec<_>>();
+    let submsgs = rebalance_msgs(
+        deps.as_ref(),
+        &env,
+        &current,
+        &validators,
+        &contract.use_denom,
+    )?;
+    let removed = current
+        .into_iter()
+        .filter(|addr| !validators.iter().any(|v| &v.address == addr))
+        .collect::<Vec<_>>();
+
+    track_withdrawn_rewards(deps.branch(), &env, &submsgs, &contract.use_denom)?;
+    config.validators = validators;
+    STAKING_CONFIG.save(deps.storage, &config)?;
+
+    let mut rsp = Response::new().add_submessages(submsgs);
+    ValidatorsUpdatedEvent {
+        validators: &config.validators,
+        removed: &removed,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Move the stake off the validators which left the active set, or else undelegate the
+/// batch of unbonds once its epoch is over. Anyone may call it, the close of a round does
+/// not redelegate so a failing redelegation cannot hold up the draw. The batch waits for
+/// the next call after a redelegation, the undelegations are picked from the delegations
+/// before the messages run.
+pub fn handle_rebalance(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut config = STAKING_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoLossDisabled {})?;
+    let denom = CONTRACT_INFO.load(deps.storage)?.use_denom;
+
+    let (submsgs, removed) = drop_inactive_validators(deps.as_ref(), &env, &mut config, &denom)?;
+    if removed.is_empty() {
+        return undelegate_batch(deps, &env, &config, &denom);
+    }
+    track_withdrawn_rewards(deps.branch(), &env, &submsgs, &denom)?;
+    STAKING_CONFIG.save(deps.storage, &config)?;
+
+    let mut rsp = Response::new().add_submessages(submsgs);
+    ValidatorsUpdatedEvent {
+        validators: &config.validators,
+        removed: &removed,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
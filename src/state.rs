@@ -1,8 +1,12 @@
+use std::fmt;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Empty, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
+
+use crate::msg::InstantiateMsg;
 
 // contract info struct
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -45,6 +49,18 @@ pub struct Investment {
     pub in_progress: bool,
     pub first_winner: Option<Winner>,
     pub second_winner: Option<Winner>,
+    #[serde(default)]
+    pub ticket_count: u64, // tickets sold in ticket mode, ids are 1..=ticket_count
+    #[serde(default)]
+    pub deadline: Option<Timestamp>, // the round can be closed by sudo after this time
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(default)]
+    pub sponsored_amount: Uint128, // added to the prize, not drawn
+    #[serde(default)]
+    pub claims_expire_at: Option<Timestamp>, // claims of the round can be swept after this time
+    #[serde(default)]
+    pub swept_amount: Uint128, // expired claims added to the prize, not drawn
 }
 
 impl Investment {
@@ -55,10 +71,248 @@ impl Investment {
             in_progress: true,
             first_winner: None,
             second_winner: None,
+            ticket_count: 0,
+            deadline: None,
+            cancelled: false,
+            sponsored_amount: Uint128::zero(),
+            claims_expire_at: None,
+            swept_amount: Uint128::zero(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OverpaymentPolicy {
+    Refund,
+    Reject,
+}
+
+// Ticket mode config. Tickets are bought at a fixed price in `use_denom`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TicketConfig {
+    pub price: Uint128,
+    pub overpayment: OverpaymentPolicy,
+}
+
+// Limits on the `use_denom` entries of a round, investments and tickets alike. An amount
+// over a cap is rejected, or accepted up to the cap with the rest refunded, by `overflow`.
+// Tickets cannot be split and are always rejected. No other denom is accepted with limits.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InvestLimits {
+    pub min_invest: Option<Uint128>,
+    pub max_invest_per_address_per_round: Option<Uint128>,
+    pub max_pot: Option<Uint128>,
+    pub overflow: OverpaymentPolicy,
+}
+
+// Release of large first prizes, and lotto top tier prizes, over time instead of at once
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingConfig {
+    pub threshold: Uint128, // prizes sent below this, what is left after a reinvest counts
+    pub duration: u64,      // seconds over which a prize unlocks linearly
+}
+
+// A prize unlocking linearly from `start` to `end`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingSchedule {
+    pub total: Uint128,
+    pub claimed: Uint128,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+// Where the claims left unclaimed past their window go
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepTarget {
+    Pot, // use_denom goes to the prize of the current round, other denoms to the fees
+    Treasury { address: String },
+}
+
+// Claim window of the prizes and refunds of a round, counted from its close
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimExpiry {
+    pub window: u64, // seconds
+    pub sweep_to: SweepTarget,
+}
+
+// Allowlist mode. Members are written by the admin, or join with a proof against
+// `merkle_root`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowlistConfig {
+    pub enabled: bool,
+    pub merkle_root: Option<Binary>, // sha256 merkle root of the allowed addresses
+}
+
+// Deposit limits of a player in `use_denom`, over rolling windows
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct DepositLimit {
+    pub per_day: Option<Uint128>,
+    pub per_week: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingDepositLimit {
+    pub limit: DepositLimit,
+    pub effective_at: Timestamp,
+}
+
+// Responsible-play settings a player put on themselves
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PlayerLimits {
+    pub excluded_until: Option<Timestamp>,
+    pub deposit_limit: DepositLimit,
+    pub pending: Option<PendingDepositLimit>, // a looser limit waiting for its delay
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ReferralStats {
+    pub referees: u64,
+    pub earned: Uint128,    // in `use_denom`, claimed or not
+    pub claimable: Uint128,
+}
+
+// Funds a sponsor added to the prize of a round
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Sponsorship {
+    pub amount: Uint128,
+    pub message: Option<String>, // the last message sent with the funds
+}
+
+// Dividend checkpoint of an LTT holder
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct HolderDividends {
+    pub index: Decimal, // reward index at the last settlement
+    pub pending: Uint128,
+}
+
+// Entries prepaid for the coming rounds, the escrow left is amount_per_round * remaining_rounds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Subscription {
+    pub amount_per_round: Uint128,
+    pub remaining_rounds: u32,
+    pub start_round: u32, // first round the subscriber is entered in
+}
+
+// How far the subscribers were entered in a round
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SubscriptionProgress {
+    pub round: u32,
+    pub last: Option<Addr>, // last subscriber of the batches so far
+    pub done: bool,
+}
+
+// cw721 collection instantiated for the tickets
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TicketNftConfig {
+    pub code_id: u64,
+    pub name: String,
+    pub symbol: String,
+}
+
+// Pick-N lotto mode config. A ticket picks `pick` distinct numbers out of 1..=max_number.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LottoConfig {
+    pub pick: u8,
+    pub max_number: u8,
+    pub price: Uint128,
+    pub tiers: Vec<LottoTier>,
+}
+
+// Share (percent of the pot) split among the tickets matching `matches` numbers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LottoTier {
+    pub matches: u8,
+    pub ratio: u8,
+}
+
+// Hash chain committed by the owner for the lotto seeds. `tip` is the last revealed link,
+// each round reveals the next one, which ends its ticket sales.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LottoChain {
+    pub tip: Binary,
+    pub remaining: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LottoResult {
+    pub seed: Binary,
+    pub numbers: Vec<u8>,
+    pub tiers: Vec<TierResult>,
+    pub rollover: Uint128, // carried to the next round
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TierResult {
+    pub matches: u8,
+    pub winners: u64,
+    pub prize: Uint128, // per winning ticket
+}
+
+// Instant-win scratch card config, a play costs `price` in `use_denom`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScratchConfig {
+    pub price: Uint128,
+    pub prizes: Vec<ScratchPrize>,
+}
+
+// Wins `multiplier` x price with a chance of 1 in `odds`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScratchPrize {
+    pub odds: u64,
+    pub multiplier: u64,
+}
+
+// Hash chain committed by the owner. `tip` is the last revealed link, each play takes
+// one of the `remaining` links and is settled when its link is revealed, in play order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScratchChain {
+    pub tip: Binary,
+    pub remaining: u64,
+    pub plays: u64,
+    pub settled: u64,
+}
+
+// A scratch card waiting for the reveal of its link
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScratchPlay {
+    pub who: Addr,
+    pub played_at: Timestamp,
+}
+
+// Prize pool of an accepted denom other than `use_denom`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Pool {
+    pub denom: String,
+    pub total_amount: Uint128,
+    pub first_winner: Option<Winner>,
+    pub second_winner: Option<Winner>,
+}
+
+impl Pool {
+    pub fn new(denom: &str) -> Pool {
+        Self {
+            denom: denom.to_string(),
+            total_amount: Uint128::zero(),
+            first_winner: None,
+            second_winner: None,
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AcceptedDenom {
+    pub denom: String,
+    pub exchange_ratio: u128, // coin: token = 1: exchange_ratio
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payout {
+    pub addr: String,
+    pub amount: Coin,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Exchange {
     pub round: u32,
@@ -66,6 +320,114 @@ pub struct Exchange {
     // pub requesters: Vec<Addr>,
 }
 
+// No-loss (prize-savings) mode config. Invested principal is delegated to `validators`
+// and only the staking rewards are drawn as prizes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingConfig {
+    pub validators: Vec<WeightedValidator>,
+    pub unbonding_period: u64, // seconds
+}
+
+// Validator with its share of the staked pot
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WeightedValidator {
+    pub address: String,
+    pub weight: u64,
+}
+
+// Undelegation sent to the chain, matured at `matures_at`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorUnbonding {
+    pub validator: String,
+    pub amount: Uint128,
+    pub matures_at: Timestamp,
+}
+
+// Principal being unbonded for a player, released with the undelegation of its batch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Unbonding {
+    pub amount: Uint128,
+    pub batch: u64,
+}
+
+// Unbonds gathered since the last undelegation, undelegated together once per epoch
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct UnbondBatch {
+    pub id: u64,
+    pub amount: Uint128,
+    pub last_undelegated_at: Option<Timestamp>,
+}
+
+// Undelegations of a batch waiting for their completion time from the chain, the last
+// `entries` of the unbonding queue
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingUnbond {
+    pub batch: u64,
+    pub entries: u32,
+    pub replies_left: u32,
+}
+
+// Template of the lotteries created by the factory
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotteryTemplate {
+    pub code_id: u64,
+    pub msg: InstantiateMsg,
+}
+
+// Lottery instance created by the factory
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotteryEntry {
+    pub template: String,
+    pub label: String,
+    pub created_at: Timestamp,
+}
+
+// Privileged roles. The contract owner holds all of them.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,     // config, denoms, validators, factory and roles
+    Operator,  // closes rounds
+    Guardian,  // pauses
+    Treasurer, // house bankroll and fees
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Admin, Role::Operator, Role::Guardian, Role::Treasurer];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Operator => "operator",
+            Role::Guardian => "guardian",
+            Role::Treasurer => "treasurer",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Operations an emergency pause can stop
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PausableOperation {
+    Invest, // investments, tickets and scratch cards
+    Close,
+    Transfer,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseInfo {
+    pub reason: String,
+    pub operations: Vec<PausableOperation>,
+    pub paused_by: Addr,
+    pub paused_at: Timestamp,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Current {
     pub round: u32,
@@ -73,12 +435,96 @@ pub struct Current {
 }
 
 pub const CURRENT: Item<Current> = Item::new("current");
+pub const ROUND_DURATION: Item<u64> = Item::new("round_duration"); // seconds
+pub const INVEST_LIMITS: Item<InvestLimits> = Item::new("invest_limits");
+pub const VESTING_CONFIG: Item<VestingConfig> = Item::new("vesting_config");
+// <(winner, round), schedule> of the top prizes being vested
+pub const VESTINGS: Map<(&Addr, u32), VestingSchedule> = Map::new("vestings");
 pub const CONTRACT_INFO: Item<ContractInfo> = Item::new("contract_info");
-pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
+// the total supply, and the balances below, are kept by height for historical reads
+pub const TOKEN_INFO: SnapshotItem<TokenInfo> = SnapshotItem::new(
+    "token_info",
+    "token_info__checkpoints",
+    "token_info__changelog",
+    Strategy::EveryBlock,
+);
 pub const STAKING: Item<Uint128> = Item::new("staking_amount");
-pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+pub const STAKING_CONFIG: Item<StakingConfig> = Item::new("staking_config");
+pub const PRINCIPALS: Map<&Addr, Uint128> = Map::new("principals");
+pub const UNBONDINGS: Map<&Addr, Vec<Unbonding>> = Map::new("unbondings");
+pub const UNBONDING_QUEUE: Item<Vec<ValidatorUnbonding>> = Item::new("unbonding_queue");
+pub const PENDING_UNBOND: Item<PendingUnbond> = Item::new("pending_unbond");
+pub const UNBOND_BATCH: Item<UnbondBatch> = Item::new("unbond_batch"); // the open batch
+pub const UNBOND_RELEASES: Map<u64, Timestamp> = Map::new("unbond_releases"); // <batch, release_at>
+// rewards the chain paid out on delegation changes, drawn at the next close
+pub const UNDRAWN_REWARDS: Item<Uint128> = Item::new("undrawn_rewards");
+pub const BALANCES: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "balance",
+    "balance__checkpoints",
+    "balance__changelog",
+    Strategy::EveryBlock,
+);
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles"); // <(role, member)>
+pub const PAUSE: Item<PauseInfo> = Item::new("pause");
+pub const ALLOWLIST_CONFIG: Item<AllowlistConfig> = Item::new("allowlist_config");
+pub const ALLOWLIST: Map<&Addr, Empty> = Map::new("allowlist");
+// <member, merkle root it was proven under>, a member only while the root is current
+pub const PROVEN_MEMBERS: Map<&Addr, Binary> = Map::new("proven_members");
+pub const BLOCKLIST: Map<&Addr, Empty> = Map::new("blocklist");
+pub const PLAYER_LIMITS: Map<&Addr, PlayerLimits> = Map::new("player_limits");
+pub const REFERRAL_SHARE: Item<u8> = Item::new("referral_share"); // percent of the owner cut
+pub const REFERRERS: Map<&Addr, Addr> = Map::new("referrers"); // <player, referrer>
+// players who entered once, a referrer is only bound before
+pub const INVESTED: Map<&Addr, Empty> = Map::new("invested");
+pub const REFERRAL_STATS: Map<&Addr, ReferralStats> = Map::new("referral_stats");
+pub const DIVIDEND_SHARE: Item<u8> = Item::new("dividend_share"); // percent of the owner cut
+// `use_denom` paid per LTT since the start, holders earn its growth on their balance
+pub const DIVIDEND_INDEX: Item<Decimal> = Item::new("dividend_index");
+pub const DIVIDEND_HOLDERS: Map<&Addr, HolderDividends> = Map::new("dividend_holders");
+// percent of their prizes a winner reinvests into the next round
+pub const AUTO_REINVEST: Map<&Addr, u8> = Map::new("auto_reinvest");
+pub const DEPOSITS: Map<(&Addr, u64), Uint128> = Map::new("deposits"); // <(player, hour), amount>
+
+pub const ACCEPTED_DENOMS: Map<&str, u128> = Map::new("accepted_denoms"); // <denom, exchange_ratio>
+pub const CLAIMABLE: Map<(&Addr, &str), Uint128> = Map::new("claimable");
+// owner cut of the rounds per denom, after referrals and dividends, until a treasurer withdraws
+pub const FEES: Map<&str, Uint128> = Map::new("fees");
+pub const CLAIM_EXPIRY: Item<ClaimExpiry> = Item::new("claim_expiry");
+// the part of CLAIMABLE owed by each round, kept when claims expire
+pub const ROUND_CLAIMS: Map<(String, &Addr, &str), Uint128> = Map::new("round_claims");
+pub const CLAIM_ROUNDS: Map<(&Addr, String), Empty> = Map::new("claim_rounds"); // <(who, round)>
+// payouts of the last close, looked up by `reply` when a send fails
+pub const PENDING_PAYOUTS: Item<Vec<Payout>> = Item::new("pending_payouts");
 
 pub const INVESTMENTS: Map<String, Investment> = Map::new("investments"); // <round, Investment>
 pub const INVESTORS: Map<(String, &Addr), Uint128> = Map::new("investors");
+pub const REDEEM_RATE: Item<Uint128> = Item::new("redeem_rate"); // LTT per unit of bonus weight
+// <(round, player), weight> bought with LTT, drawn like investments but not in the prize
+pub const BONUS_WEIGHTS: Map<(String, &Addr), Uint128> = Map::new("bonus_weights");
+pub const SPONSORS: Map<(String, &Addr), Sponsorship> = Map::new("sponsors"); // <(round, sponsor)>
+pub const SUBSCRIPTIONS: Map<&Addr, Subscription> = Map::new("subscriptions");
+pub const SUBSCRIPTION_PROGRESS: Item<SubscriptionProgress> = Item::new("subscription_progress");
+pub const TICKET_CONFIG: Item<TicketConfig> = Item::new("ticket_config");
+pub const TICKETS: Map<(String, u64), Addr> = Map::new("tickets"); // <(round, ticket id), owner>
+pub const TICKET_NFT: Item<Addr> = Item::new("ticket_nft");
+pub const LOTTO_CONFIG: Item<LottoConfig> = Item::new("lotto_config");
+// <(round, ticket id), numbers>, owners are kept in TICKETS
+pub const LOTTO_NUMBERS: Map<(String, u64), Vec<u8>> = Map::new("lotto_numbers");
+pub const LOTTO_RESULTS: Map<String, LottoResult> = Map::new("lotto_results");
+pub const LOTTO_ROLLOVER: Item<Uint128> = Item::new("lotto_rollover");
+pub const LOTTO_CHAIN: Item<LottoChain> = Item::new("lotto_chain");
+pub const LOTTO_SEEDS: Map<String, Binary> = Map::new("lotto_seeds"); // <round, revealed link>
+pub const SCRATCH_CONFIG: Item<ScratchConfig> = Item::new("scratch_config");
+pub const SCRATCH_CHAIN: Item<ScratchChain> = Item::new("scratch_chain");
+pub const SCRATCH_PLAYS: Map<u64, ScratchPlay> = Map::new("scratch_plays"); // <play, ScratchPlay> to settle
+pub const SCRATCH_BANKROLL: Item<Uint128> = Item::new("scratch_bankroll"); // house funds for prizes
+pub const OWNED_TICKETS: Map<(String, &Addr, u64), Empty> = Map::new("owned_tickets");
+pub const POOLS: Map<(String, &str), Pool> = Map::new("pools"); // <(round, denom), Pool>
+pub const POOL_INVESTORS: Map<(String, &str, &Addr), Uint128> = Map::new("pool_investors");
+pub const FACTORY: Item<bool> = Item::new("factory"); // creates lotteries from templates when true
+pub const LOTTERY_TEMPLATES: Map<&str, LotteryTemplate> = Map::new("lottery_templates");
+pub const LOTTERIES: Map<&Addr, LotteryEntry> = Map::new("lotteries");
+// lottery waiting for its instantiate reply
+pub const PENDING_LOTTERY: Item<LotteryEntry> = Item::new("pending_lottery");
 pub const EXCHANGES: Map<String, Exchange> = Map::new("exchanges");
 pub const EXCHANGERS: Map<(String, &Addr), Uint128> = Map::new("exchangers");
@@ -0,0 +1,178 @@
+use cosmwasm_std::{BankMsg, Coin, DepsMut, Env, MessageInfo, Order, Response, Uint128};
+use cw_storage_plus::Bound;
+
+use crate::access::ensure_allowed;
+use crate::contract::{enter_on_behalf, is_invest_mode, must_pay};
+use crate::error::ContractError;
+use crate::event::{Event, SubscribedEvent, SubscriptionsProcessedEvent, UnsubscribedEvent};
+use crate::pause::ensure_not_paused;
+use crate::referral::bind_referrer;
+use crate::responsible::record_deposit;
+use crate::state::{
+    PausableOperation, Subscription, SubscriptionProgress, CONTRACT_INFO, CURRENT, INVESTMENTS,
+    SUBSCRIPTIONS, SUBSCRIPTION_PROGRESS,
+};
+
+// subscribers entered when a round opens, the rest by ProcessSubscriptions
+pub const SUBSCRIPTION_BATCH: u32 = 30;
+const MAX_SUBSCRIPTION_BATCH: u32 = 100;
+
+/// Escrow `rounds` entries of `amount_per_round`, entered from the next round on. The
+/// escrow counts against the deposit limits when it is paid, not when entered, and a
+/// referrer is bound as on a first entry.
+pub fn handle_subscribe(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rounds: u32,
+    amount_per_round: Uint128,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    // entries are plain investments, the other modes are not supported
+    if !is_invest_mode(deps.storage)? {
+        return Err(ContractError::InvalidParams {});
+    }
+    if rounds == 0 || amount_per_round.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if SUBSCRIPTIONS.has(deps.storage, &info.sender) {
+        return Err(ContractError::AlreadySubscribed {});
+    }
+    ensure_allowed(deps.storage, &info.sender)?;
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let paid = must_pay(&info, &contract.use_denom)?;
+    let expected = amount_per_round.checked_mul(Uint128::from(rounds))?;
+    if paid != expected {
+        return Err(ContractError::InvalidPayment {
+            expected,
+            received: paid,
+        });
+    }
+    record_deposit(deps.storage, &env, &info.sender, paid)?;
+    bind_referrer(deps.storage, deps.api, &info.sender, referrer)?;
+
+    let start_round = CURRENT.load(deps.storage)?.round + 1;
+    SUBSCRIPTIONS.save(
+        deps.storage,
+        &info.sender,
+        &Subscription {
+            amount_per_round,
+            remaining_rounds: rounds,
+            start_round,
+        },
+    )?;
+
+    let mut rsp = Response::new();
+    SubscribedEvent {
+        who: info.sender.as_str(),
+        rounds,
+        amount_per_round,
+        start_round,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// Cancel the subscription of the sender and refund the rounds not entered yet.
+pub fn handle_unsubscribe(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let subscription = SUBSCRIPTIONS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NotSubscribed {})?;
+    SUBSCRIPTIONS.remove(deps.storage, &info.sender);
+
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let refund = subscription
+        .amount_per_round
+        .checked_mul(Uint128::from(subscription.remaining_rounds))?;
+    let mut rsp = Response::new().add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin::new(refund.u128(), &contract.use_denom)],
+    });
+    UnsubscribedEvent {
+        who: info.sender.as_str(),
+        refund,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+/// Enter up to `limit` subscribers in the current round, going on from where the last
+/// batch of the round stopped. A subscriber the round does not accept, e.g. over a limit
+/// or self-excluded, sits the round out and keeps the entry for a later one.
+pub fn enter_subscribers(
+    mut deps: DepsMut,
+    env: &Env,
+    limit: u32,
+) -> Result<(u32, bool), ContractError> {
+    let round = CURRENT.load(deps.storage)?.round;
+    let progress = SUBSCRIPTION_PROGRESS
+        .may_load(deps.storage)?
+        .filter(|p| p.round == round);
+    if progress.as_ref().is_some_and(|p| p.done)
+        || ensure_not_paused(deps.storage, PausableOperation::Invest).is_err()
+    {
+        return Ok((0, true));
+    }
+    let start = progress.and_then(|p| p.last);
+
+    let batch = SUBSCRIPTIONS
+        .range(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit as usize)
+        .collect::<Result<Vec<_>, _>>()?;
+    let done = batch.len() < limit as usize;
+    let last = batch.last().map(|(addr, _)| addr.clone()).or(start);
+
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let mut investment = INVESTMENTS.load(deps.storage, round.to_string())?;
+    let mut entered = 0;
+    for (addr, mut subscription) in batch {
+        let amount = subscription.amount_per_round;
+        if round < subscription.start_round
+            || !enter_on_behalf(
+                deps.branch(),
+                env,
+                &contract,
+                &mut investment,
+                &addr,
+                amount,
+                true,
+            )?
+        {
+            continue;
+        }
+
+        subscription.remaining_rounds -= 1;
+        if subscription.remaining_rounds == 0 {
+            SUBSCRIPTIONS.remove(deps.storage, &addr);
+        } else {
+            SUBSCRIPTIONS.save(deps.storage, &addr, &subscription)?;
+        }
+        entered += 1;
+    }
+    INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
+    SUBSCRIPTION_PROGRESS.save(deps.storage, &SubscriptionProgress { round, last, done })?;
+
+    Ok((entered, done))
+}
+
+/// Enter the next batch of subscribers in the current round. Anyone may call it once the
+/// batch entered at the opening of the round was not enough.
+pub fn handle_process_subscriptions(
+    deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit
+        .unwrap_or(SUBSCRIPTION_BATCH)
+        .min(MAX_SUBSCRIPTION_BATCH);
+    let (entered, done) = enter_subscribers(deps, &env, limit)?;
+
+    let mut rsp = Response::new();
+    SubscriptionsProcessedEvent { entered, done }.add_attributes(&mut rsp);
+    Ok(rsp)
+}
@@ -0,0 +1,161 @@
+use cosmwasm_std::{Deps, DepsMut, Env, Order, Response, StdResult, Uint128};
+
+use crate::contract::{burn_token, close_round, exchange_ratio_of, next_investment};
+use crate::error::ContractError;
+use crate::event::{DueRoundsCheckedEvent, Event, RoundCancelledEvent};
+use crate::expiry::{claims_deadline, credit_claim};
+use crate::pause::ensure_not_paused;
+use crate::state::{
+    Current, PausableOperation, BALANCES, BONUS_WEIGHTS, CONTRACT_INFO, CURRENT, INVESTMENTS,
+    INVESTORS, LOTTO_CONFIG, LOTTO_SEEDS, POOL_INVESTORS, PRINCIPALS, SPONSORS, STAKING_CONFIG,
+};
+use crate::subscription::{enter_subscribers, SUBSCRIPTION_BATCH};
+
+/// Whether the round has anyone to draw, in the same way `close_round` looks for them.
+fn has_entries(deps: Deps, round: u32) -> StdResult<bool> {
+    let investors = match STAKING_CONFIG.may_load(deps.storage)? {
+        Some(_) => PRINCIPALS
+            .range(deps.storage, None, None, Order::Ascending)
+            .any(|item| item.is_ok_and(|(_, amount)| !amount.is_zero())),
+        None => INVESTORS
+            .prefix(round.to_string())
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some(),
+    };
+    // bonus weight, and tickets redeemed with LTT, enter players who paid nothing
+    let tickets = INVESTMENTS.load(deps.storage, round.to_string())?.ticket_count > 0;
+    let bonus = BONUS_WEIGHTS
+        .prefix(round.to_string())
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    let pool_investors = POOL_INVESTORS
+        .sub_prefix(round.to_string())
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    Ok(investors || tickets || bonus || pool_investors)
+}
+
+/// Close the current round once its deadline has passed, without an operator. A due round
+/// nobody entered is given another period instead of failing every scheduled call.
+pub fn handle_close_due_rounds(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage, PausableOperation::Close)?;
+    let round = CURRENT.load(deps.storage)?.round;
+    let mut investment = INVESTMENTS.load(deps.storage, round.to_string())?;
+    let due = investment
+        .deadline
+        .is_some_and(|deadline| env.block.time >= deadline);
+    let mut rsp = Response::new();
+    if !due {
+        DueRoundsCheckedEvent {
+            extended: None,
+            awaiting_seed: None,
+        }
+        .add_attributes(&mut rsp);
+        return Ok(rsp);
+    }
+
+    if !has_entries(deps.as_ref(), round)? {
+        investment.deadline = next_investment(deps.storage, &env, round)?.deadline;
+        INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
+        DueRoundsCheckedEvent {
+            extended: Some(round),
+            awaiting_seed: None,
+        }
+        .add_attributes(&mut rsp);
+        return Ok(rsp);
+    }
+    // a lotto round is drawn from its seed, which the owner reveals after the deadline
+    if LOTTO_CONFIG.may_load(deps.storage)?.is_some()
+        && !LOTTO_SEEDS.has(deps.storage, round.to_string())
+    {
+        DueRoundsCheckedEvent {
+            extended: None,
+            awaiting_seed: Some(round),
+        }
+        .add_attributes(&mut rsp);
+        return Ok(rsp);
+    }
+    close_round(deps, env)
+}
+
+/// Cancel the current round without a draw. Its investments and sponsorships are moved to
+/// the claimable balances of their senders and the LTT minted for the investments is burned,
+/// as much of it as the investors still hold. In no-loss mode the principals stay deposited,
+/// with their LTT, and the rewards carry over to the next round.
+pub fn handle_force_cancel(deps: DepsMut, env: Env, round: u32) -> Result<Response, ContractError> {
+    let current = CURRENT.load(deps.storage)?;
+    if round != current.round {
+        return Err(ContractError::InvalidRound { round });
+    }
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+
+    let mut refunds = match STAKING_CONFIG.may_load(deps.storage)? {
+        Some(_) => vec![],
+        None => INVESTORS
+            .prefix(round.to_string())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(addr, amount)| (addr, contract.use_denom.clone(), amount)))
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+    let pool_refunds = POOL_INVESTORS
+        .sub_prefix(round.to_string())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|((denom, addr), amount)| (addr, denom, amount)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let sponsor_refunds = SPONSORS
+        .prefix(round.to_string())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(addr, s)| (addr, contract.use_denom.clone(), s.amount)))
+        .collect::<StdResult<Vec<_>>>()?;
+    refunds.extend(pool_refunds);
+    let mut burned = Uint128::zero();
+    for (addr, denom, amount) in refunds.iter() {
+        credit_claim(deps.storage, round, addr, denom, *amount)?;
+        // a denom removed since keeps the LTT, its ratio is gone
+        let ratio = match exchange_ratio_of(deps.storage, &contract, denom) {
+            Ok(ratio) => ratio,
+            Err(_) => continue,
+        };
+        let balance = BALANCES.may_load(deps.storage, addr)?.unwrap_or_default();
+        let amount = amount.checked_mul(Uint128::from(ratio))?.min(balance);
+        if !amount.is_zero() {
+            burn_token(deps.storage, &env, addr, amount)?;
+            burned = burned.checked_add(amount)?;
+        }
+    }
+    for (addr, denom, amount) in sponsor_refunds.iter() {
+        credit_claim(deps.storage, round, addr, denom, *amount)?;
+    }
+
+    let mut investment = INVESTMENTS.load(deps.storage, round.to_string())?;
+    investment.in_progress = false;
+    investment.cancelled = true;
+    investment.claims_expire_at = claims_deadline(deps.storage, &env)?;
+    INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
+
+    CURRENT.save(
+        deps.storage,
+        &Current {
+            round: round + 1,
+            exchange_round: current.exchange_round,
+        },
+    )?;
+    // expired claims swept into the prize owe nobody a refund, they go to the next one
+    let mut new_investment = next_investment(deps.storage, &env, round + 1)?;
+    new_investment.swept_amount = investment.swept_amount;
+    INVESTMENTS.save(deps.storage, (round + 1).to_string(), &new_investment)?;
+    let (entered, _) = enter_subscribers(deps, &env, SUBSCRIPTION_BATCH)?;
+
+    let mut rsp = Response::new();
+    RoundCancelledEvent {
+        round,
+        refunds: (refunds.len() + sponsor_refunds.len()) as u64,
+        burned,
+        subscribers_entered: entered,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
@@ -0,0 +1,139 @@
+use cosmwasm_std::{BankMsg, Coin, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Uint128};
+use sha2::{Digest, Sha256};
+
+use crate::access::ensure_allowed;
+use crate::contract::{mint_token, must_pay};
+use crate::error::ContractError;
+use crate::event::{Event, InvestedEvent, TicketsBoughtEvent};
+use crate::limits::ensure_invest_limits;
+use crate::nft::mint_ticket_msg;
+use crate::referral::bind_referrer;
+use crate::responsible::record_deposit;
+use crate::state::{
+    OverpaymentPolicy, CONTRACT_INFO, CURRENT, INVESTMENTS, INVESTORS, OWNED_TICKETS, TICKETS,
+    TICKET_CONFIG, TICKET_NFT,
+};
+
+// bounds the storage writes of a single purchase
+pub const MAX_TICKETS_PER_BUY: u64 = 100;
+
+pub fn handle_buy_tickets(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    count: u64,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = TICKET_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::TicketsDisabled {})?;
+    if count == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if count > MAX_TICKETS_PER_BUY {
+        return Err(ContractError::InvalidParams {});
+    }
+    ensure_allowed(deps.storage, &info.sender)?;
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let paid = must_pay(&info, &contract.use_denom)?;
+
+    // exactly count x price, the excess is refunded or rejected by the policy
+    let cost = config.price.checked_mul(Uint128::from(count))?;
+    let refund = paid
+        .checked_sub(cost)
+        .map_err(|_| ContractError::InvalidPayment {
+            expected: cost,
+            received: paid,
+        })?;
+    if !refund.is_zero() && config.overpayment == OverpaymentPolicy::Reject {
+        return Err(ContractError::InvalidPayment {
+            expected: cost,
+            received: paid,
+        });
+    }
+
+    let round = CURRENT.load(deps.storage)?.round;
+    let mut investment = INVESTMENTS
+        .may_load(deps.storage, round.to_string())?
+        .ok_or(ContractError::InvalidRound { round })?;
+    ensure_invest_limits(deps.storage, &investment, &info.sender, cost)?;
+    record_deposit(deps.storage, &env, &info.sender, cost)?;
+    bind_referrer(deps.storage, deps.api, &info.sender, referrer)?;
+
+    // sequential ticket ids per round
+    let first_ticket = investment.ticket_count + 1;
+    for id in first_ticket..first_ticket + count {
+        TICKETS.save(deps.storage, (round.to_string(), id), &info.sender)?;
+        OWNED_TICKETS.save(
+            deps.storage,
+            (round.to_string(), &info.sender, id),
+            &Empty {},
+        )?;
+    }
+    investment.ticket_count += count;
+    investment.total_amount = investment.total_amount.checked_add(cost)?;
+    INVESTMENTS.save(deps.storage, round.to_string(), &investment)?;
+    INVESTORS.update(
+        deps.storage,
+        (round.to_string(), &info.sender),
+        |a| -> StdResult<_> { Ok(a.unwrap_or_default().checked_add(cost)?) },
+    )?;
+
+    // mint the tickets in the collection
+    let mut rsp = Response::new();
+    if let Some(collection) = TICKET_NFT.may_load(deps.storage)? {
+        for id in first_ticket..first_ticket + count {
+            rsp = rsp.add_message(mint_ticket_msg(&collection, &info.sender, round, id)?);
+        }
+    }
+    if !refund.is_zero() {
+        rsp = rsp.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(refund.u128(), &contract.use_denom)],
+        });
+    }
+
+    let exchange_amount = cost
+        .checked_mul(Uint128::new(contract.exchange_ratio))
+        .map_err(|e| ContractError::CustomError { val: e.to_string() })?;
+    mint_token(deps, &env, &info.sender, exchange_amount)?;
+
+    TicketsBoughtEvent {
+        round,
+        who: info.sender.as_str(),
+        first_ticket,
+        count,
+    }
+    .add_attributes(&mut rsp);
+    InvestedEvent {
+        round,
+        who: info.sender.as_str(),
+        amount: cost,
+        denom: &contract.use_denom,
+    }
+    .add_attributes(&mut rsp);
+
+    Ok(rsp)
+}
+
+/// Pick the first and second winning ticket ids among `count` sold tickets from the seed.
+/// The second ticket is drawn among the others, a single ticket wins both.
+pub fn draw_tickets(seed: &[u8], count: u64) -> (u64, u64) {
+    let pick = |nonce: u32, range: u64| {
+        let hash = Sha256::new()
+            .chain_update(seed)
+            .chain_update(nonce.to_be_bytes())
+            .finalize();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hash[..8]);
+        u64::from_be_bytes(bytes) % range
+    };
+    let first = pick(0, count) + 1;
+    if count == 1 {
+        return (first, first);
+    }
+    // skip over the first ticket
+    let second = (first + pick(1, count - 1)) % count + 1;
+
+    (first, second)
+}
@@ -0,0 +1,89 @@
+use cosmwasm_std::{
+    Addr, BankMsg, Coin, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage, Timestamp,
+    Uint128,
+};
+
+use crate::error::ContractError;
+use crate::event::{VestedClaimedEvent, Event};
+use crate::state::{Payout, VestingSchedule, CONTRACT_INFO, VESTINGS, VESTING_CONFIG};
+
+/// Part of `schedule` unlocked at `now`, claimed or not.
+pub fn unlocked(schedule: &VestingSchedule, now: Timestamp) -> Uint128 {
+    if now >= schedule.end {
+        return schedule.total;
+    }
+    let elapsed = now.seconds().saturating_sub(schedule.start.seconds());
+    let duration = schedule.end.seconds() - schedule.start.seconds();
+    schedule
+        .total
+        .multiply_ratio(elapsed as u128, duration as u128)
+}
+
+/// Vest a top prize `payout` of `round` when what is left to send of it, after a reinvested
+/// part, reaches the threshold. The payout is zeroed and the vested amount returned. Top
+/// prizes of a winner in the same round share a schedule.
+pub fn vest_prize(
+    storage: &mut dyn Storage,
+    env: &Env,
+    round: u32,
+    payout: &mut Payout,
+) -> Result<Uint128, ContractError> {
+    let amount = payout.amount.amount;
+    let config = match VESTING_CONFIG.may_load(storage)? {
+        Some(config) if !amount.is_zero() && amount >= config.threshold => config,
+        _ => return Ok(Uint128::zero()),
+    };
+
+    let winner = Addr::unchecked(&payout.addr);
+    VESTINGS.update(storage, (&winner, round), |schedule| -> StdResult<_> {
+        let mut schedule = schedule.unwrap_or(VestingSchedule {
+            total: Uint128::zero(),
+            claimed: Uint128::zero(),
+            start: env.block.time,
+            end: env.block.time.plus_seconds(config.duration),
+        });
+        schedule.total = schedule.total.checked_add(amount)?;
+        Ok(schedule)
+    })?;
+    payout.amount.amount = Uint128::zero();
+    Ok(amount)
+}
+
+/// Send the sender what unlocked so far of their vested prizes.
+pub fn handle_claim_vested(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let schedules = VESTINGS
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut amount = Uint128::zero();
+    for (round, mut schedule) in schedules {
+        let released = unlocked(&schedule, env.block.time) - schedule.claimed;
+        amount = amount.checked_add(released)?;
+        schedule.claimed += released;
+        if schedule.claimed == schedule.total {
+            VESTINGS.remove(deps.storage, (&info.sender, round));
+        } else {
+            VESTINGS.save(deps.storage, (&info.sender, round), &schedule)?;
+        }
+    }
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    let contract = CONTRACT_INFO.load(deps.storage)?;
+    let mut rsp = Response::new().add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin::new(amount.u128(), &contract.use_denom)],
+    });
+    VestedClaimedEvent {
+        who: info.sender.as_str(),
+        amount,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}